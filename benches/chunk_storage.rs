@@ -0,0 +1,90 @@
+// Compares chunks stored as fixed size arrays and palette encoded against the Vec<TileId> chunks
+// used before. Run with cargo bench --bench chunk_storage
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use world_renderer::chunk_palette::PalettedTiles;
+use world_renderer::utils::random_tile;
+use world_renderer::world::*;
+
+// A chunk of random tiles, the worst case for the palette, and a chunk of a single tile
+fn test_chunks() -> [(&'static str, [TileId; TILES_PER_CHUNK]); 2] {
+    let mut rng = StdRng::seed_from_u64(0);
+    [
+        ("mixed", std::array::from_fn(|_| random_tile(&mut rng))),
+        ("single tile", [TileId::WATER; TILES_PER_CHUNK]),
+    ]
+}
+
+fn read_tiles(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read every tile");
+    for (name, tiles) in test_chunks() {
+        let vec_tiles = tiles.to_vec();
+        let paletted = PalettedTiles::encode(&tiles);
+        group.bench_function(format!("vec, {}", name), |b| {
+            b.iter(|| {
+                (0..TILES_PER_CHUNK)
+                    .filter_map(|index| black_box(&vec_tiles).get(index))
+                    .filter(|tile| **tile == TileId::WATER)
+                    .count()
+            })
+        });
+        group.bench_function(format!("array, {}", name), |b| {
+            b.iter(|| {
+                (0..TILES_PER_CHUNK)
+                    .map(|index| black_box(&tiles)[index])
+                    .filter(|tile| *tile == TileId::WATER)
+                    .count()
+            })
+        });
+        group.bench_function(format!("paletted, {}", name), |b| {
+            b.iter(|| {
+                (0..TILES_PER_CHUNK)
+                    .map(|index| black_box(&paletted).get(index))
+                    .filter(|tile| *tile == TileId::WATER)
+                    .count()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn clone_chunk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clone chunk");
+    let [(_, tiles), _] = test_chunks();
+    let vec_tiles = tiles.to_vec();
+    group.bench_function("vec", |b| b.iter(|| black_box(&vec_tiles).clone()));
+    group.bench_function("array", |b| b.iter(|| *black_box(&tiles)));
+    group.finish();
+}
+
+// Raw u16 ids, like world files before palette encoding, against the palette encoding
+fn serialize_chunk(c: &mut Criterion) {
+    let mut group = c.benchmark_group("serialize chunk");
+    for (name, tiles) in test_chunks() {
+        let vec_tiles = tiles.to_vec();
+        group.bench_function(format!("raw vec, {}", name), |b| {
+            b.iter(|| {
+                black_box(&vec_tiles)
+                    .iter()
+                    .flat_map(|tile| tile.0.to_le_bytes())
+                    .collect::<Vec<u8>>()
+            })
+        });
+        group.bench_function(format!("paletted, {}", name), |b| {
+            b.iter(|| PalettedTiles::encode(black_box(&tiles)).to_bytes())
+        });
+        let bytes = PalettedTiles::encode(&tiles).to_bytes();
+        group.bench_function(format!("paletted decode, {}", name), |b| {
+            b.iter(|| {
+                PalettedTiles::read_from(&mut black_box(bytes.as_slice()))
+                    .unwrap()
+                    .decode()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, read_tiles, clone_chunk, serialize_chunk);
+criterion_main!(benches);
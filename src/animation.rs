@@ -0,0 +1,69 @@
+use serde::Deserialize;
+
+use crate::assets::atlas_lookup;
+use crate::assets::AtlasRect;
+use crate::tile_registry::TileId;
+use crate::world::*;
+
+pub const MAX_ANIMATION_FRAMES: usize = 4; // Frames the autotile atlas has room for
+
+// How an animated tile cycles through its frames, set in the tile definitions
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TileAnimation {
+    pub frame_durations: Vec<f32>, // Seconds each frame is shown for, in order
+    #[serde(default)]
+    pub phase_offset: bool, // When set, every tile starts the cycle at a different point so neighbors don't animate in lockstep
+}
+
+// Animation of a tile, None for tiles that don't animate
+pub fn tile_animation(tile: &TileId) -> Option<&'static TileAnimation> {
+    tile.definition()?.animation.as_ref()
+}
+
+impl TileAnimation {
+    // Seconds for one full cycle
+    pub fn duration(&self) -> f32 {
+        self.frame_durations.iter().sum()
+    }
+
+    // Index of the frame shown at a time in seconds, for the tile at pos
+    pub fn frame_at(&self, time: f32, pos: &GlobalTilePos) -> usize {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return 0;
+        }
+        let mut cycle_time = time;
+        if self.phase_offset {
+            cycle_time += phase_offset(pos) * duration;
+        }
+        let mut cycle_time = cycle_time.rem_euclid(duration);
+        for (frame, frame_duration) in self.frame_durations.iter().enumerate() {
+            if cycle_time < *frame_duration {
+                return frame;
+            }
+            cycle_time -= frame_duration;
+        }
+        self.frame_durations.len() - 1 // Only reached through float rounding
+    }
+
+    // The atlas rects of the animation for a neighbor mask or transition sprite, with how long each
+    // is shown for
+    pub fn frames(&self, tile: &TileId, sprite: u8) -> impl Iterator<Item = (AtlasRect, f32)> + '_ {
+        let tile = *tile;
+        self.frame_durations
+            .iter()
+            .enumerate()
+            .map(move |(frame, duration)| {
+                (atlas_lookup::autotile_rect(&tile, sprite, frame), *duration)
+            })
+    }
+}
+
+// Fraction of a cycle a tile's animation is shifted by, from 0 to 1. Scrambles the position so
+// neighboring tiles get unrelated offsets.
+fn phase_offset(pos: &GlobalTilePos) -> f32 {
+    let hash = (pos.0 as u32).wrapping_mul(0x9E37_79B1) ^ (pos.1 as u32).wrapping_mul(0x85EB_CA77);
+    let hash = hash ^ (hash >> 15);
+    (hash % 1024) as f32 / 1024.0
+}
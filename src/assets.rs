@@ -1,51 +1,61 @@
-use macroquad::{
-    prelude::ImageFormat,
-    texture::{FilterMode, Texture2D},
-};
+// Png of the tile spritesheet, embedded in the binary
+pub const TILE_ATLAS_PNG: &[u8] = include_bytes!("assets/tiles/tile_atlas_padded.png");
 
-// Spritesheet for the tiles
-pub struct TileAtlas(pub Texture2D);
-
-pub struct AssetHandle {
-    pub tile_atlas: TileAtlas,
+// Area of the atlas in pixels, measured from the top left of the png
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasRect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
 }
 
-impl AssetHandle {
-    pub fn new() -> Self {
-        let embedded_tile_atlas =
-            Self::load_embedded_asset(include_bytes!("assets\\tiles\\tile_atlas_padded.png"));
-        AssetHandle {
-            tile_atlas: TileAtlas(embedded_tile_atlas),
-        }
-    }
-    pub fn load_embedded_asset(file_bytes: &[u8]) -> Texture2D {
-        let texture = Texture2D::from_file_with_format(file_bytes, Some(ImageFormat::Png));
-        texture.set_filter(FilterMode::Nearest);
-        return texture;
+impl AtlasRect {
+    pub const fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        AtlasRect { x, y, w, h }
     }
 }
 
 // Lookups for atlas / spritesheet.
 pub mod atlas_lookup {
-    use macroquad::prelude::*;
-    use once_cell::sync::Lazy;
+    use super::AtlasRect;
+    use crate::autotile::SPRITES_PER_FRAME;
+    use crate::tile_registry::{tile_registry, TileId};
 
     // Calculates position in atlas for tile, with one pixel padding
-    fn define_pos_in_atlas(x: i32, y: i32) -> Rect {
+    const fn define_pos_in_atlas(x: i32, y: i32) -> AtlasRect {
         let x = (x as f32 * (TILE_SIZE + 2.0)) + 1.0;
         let y = (y as f32 * (TILE_SIZE + 2.0)) + 1.0;
-        Rect::new(x, y, TILE_SIZE, TILE_SIZE)
+        AtlasRect::new(x, y, TILE_SIZE, TILE_SIZE)
     }
 
     // Tiles
     pub const TILE_SIZE: f32 = 8.0; // Tile size in pixels
 
-    pub const TILE_GRASS: Lazy<Rect> = Lazy::new(|| define_pos_in_atlas(0, 0));
-    pub const TILE_WATER: Lazy<Rect> = Lazy::new(|| define_pos_in_atlas(1, 0));
-    pub const TILE_SAND: Lazy<Rect> = Lazy::new(|| define_pos_in_atlas(2, 0));
-    pub const TILE_STONE: Lazy<Rect> = Lazy::new(|| define_pos_in_atlas(3, 0));
-    pub const TILE_SHALLOW_WATER: Lazy<Rect> = Lazy::new(|| define_pos_in_atlas(0, 1));
-    pub const TILE_DEEP_WATER: Lazy<Rect> = Lazy::new(|| define_pos_in_atlas(1, 1));
-    pub const TILE_DARK_STONE: Lazy<Rect> = Lazy::new(|| define_pos_in_atlas(2, 1));
-    pub const TILE_SNOW: Lazy<Rect> = Lazy::new(|| define_pos_in_atlas(3, 1));
+    // Size of the tile atlas in sprites
+    pub const TILE_ATLAS_COLUMNS: u32 = 4;
+    pub const TILE_ATLAS_ROWS: u32 = 4;
+
+    // An empty slot of the atlas, tiles missing from the registry show up black
+    pub const MISSING_TILE: AtlasRect = define_pos_in_atlas(3, 3);
+
+    // Where a tile's sprite is in the tile atlas, from its definition
+    pub fn tile_rect(tile: &TileId) -> AtlasRect {
+        match tile.definition() {
+            Some(definition) => {
+                define_pos_in_atlas(definition.atlas[0] as i32, definition.atlas[1] as i32)
+            }
+            None => MISSING_TILE,
+        }
+    }
+
+    // Autotile atlas, see autotile::build_autotile_atlas. The sprite is a neighbor mask or a
+    // transition sprite. Tiles that don't animate only have frame 0. Tiles missing from the
+    // registry get the empty row after the last tile.
+    pub fn autotile_rect(tile: &TileId, sprite: u8, frame: usize) -> AtlasRect {
+        let registry = tile_registry();
+        let row = registry.atlas_row(*tile).unwrap_or(registry.len() as u16);
+        let column = frame as i32 * SPRITES_PER_FRAME as i32 + sprite as i32;
+        define_pos_in_atlas(column, row as i32)
+    }
 }
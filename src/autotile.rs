@@ -0,0 +1,189 @@
+use image::{Rgba, RgbaImage};
+
+use crate::animation::{tile_animation, MAX_ANIMATION_FRAMES};
+use crate::assets::atlas_lookup::{self, TILE_SIZE};
+use crate::assets::TILE_ATLAS_PNG;
+use crate::tile_registry::tile_registry;
+use crate::world::*;
+
+// Bits of a neighbor mask, set when the neighbor on that side connects to the tile. North is
+// towards smaller y, like the top of the screen.
+pub const NEIGHBOR_NORTH: u8 = 1;
+pub const NEIGHBOR_EAST: u8 = 2;
+pub const NEIGHBOR_SOUTH: u8 = 4;
+pub const NEIGHBOR_WEST: u8 = 8;
+pub const NEIGHBOR_ALL: u8 = NEIGHBOR_NORTH | NEIGHBOR_EAST | NEIGHBOR_SOUTH | NEIGHBOR_WEST; // No shaded edges, used for decorations and objects
+pub const NEIGHBOR_MASKS: u8 = 16; // Number of different masks
+
+// Every animation frame of a tile has a sprite per neighbor mask, followed by a transition sprite
+// per side. A transition sprite is the tile's sprite fading out from one edge, drawn over a
+// neighboring tile of another type so the two blend into each other.
+pub const TRANSITION_SPRITES: u8 = 4;
+pub const SPRITES_PER_FRAME: u8 = NEIGHBOR_MASKS + TRANSITION_SPRITES;
+
+// How much the pixels along an edge are darkened, from the outermost pixel inwards
+const EDGE_SHADING: [f32; 2] = [0.72, 0.88];
+// Opacity of a transition sprite, from the pixels along its edge inwards
+const TRANSITION_FADE: [f32; 3] = [0.8, 0.5, 0.2];
+
+const SIDES: [u8; 4] = [NEIGHBOR_NORTH, NEIGHBOR_EAST, NEIGHBOR_SOUTH, NEIGHBOR_WEST];
+
+// Sprite index of the transition that fades in from a side of the tile it is drawn over, one of
+// the NEIGHBOR_ bits. Used with atlas_lookup::autotile_rect like a neighbor mask.
+pub fn transition_sprite(side: u8) -> u8 {
+    NEIGHBOR_MASKS + side.trailing_zeros() as u8
+}
+
+// Autotiling
+impl World {
+    // Returns which neighbors of a tile are the same tile. Neighbors in other chunks are looked
+    // up like any other tile, and neighbors that aren't loaded count as connected so the edge of
+    // the loaded area doesn't get a border.
+    pub fn neighbor_mask(&self, pos: &GlobalTilePos) -> u8 {
+        let Some(tile) = self.loaded_tile(pos) else {
+            return 0;
+        };
+        let sides = [
+            (NEIGHBOR_NORTH, GlobalTilePos(pos.0, pos.1 - 1)),
+            (NEIGHBOR_EAST, GlobalTilePos(pos.0 + 1, pos.1)),
+            (NEIGHBOR_SOUTH, GlobalTilePos(pos.0, pos.1 + 1)),
+            (NEIGHBOR_WEST, GlobalTilePos(pos.0 - 1, pos.1)),
+        ];
+        let mut mask = 0;
+        for (bit, neighbor_pos) in sides {
+            match self.loaded_tile(&neighbor_pos) {
+                Some(neighbor) if neighbor != tile => (),
+                _ => mask |= bit,
+            }
+        }
+        mask
+    }
+
+    // Returns the transitions drawn over a terrain tile, as the transition sprite and the
+    // neighbor tile it belongs to, in drawing order. Where two tile types meet, the one that comes
+    // first in the registry spreads over the other, so grass fades into sand and sand into water.
+    pub fn transitions(&self, pos: &GlobalTilePos) -> Vec<(u8, TileId)> {
+        let Some(tile) = self.loaded_tile(pos) else {
+            return Vec::new();
+        };
+        let neighbors = [
+            GlobalTilePos(pos.0, pos.1 - 1),
+            GlobalTilePos(pos.0 + 1, pos.1),
+            GlobalTilePos(pos.0, pos.1 + 1),
+            GlobalTilePos(pos.0 - 1, pos.1),
+        ];
+        let mut transitions: Vec<(u8, TileId)> = SIDES
+            .iter()
+            .zip(&neighbors)
+            .filter_map(|(side, neighbor_pos)| {
+                let neighbor = self.loaded_tile(neighbor_pos)?;
+                (neighbor.0 < tile.0).then_some((transition_sprite(*side), *neighbor))
+            })
+            .collect();
+        // The neighbor that spreads the most is drawn last, over the others
+        transitions.sort_by_key(|(sprite, neighbor)| (std::cmp::Reverse(neighbor.0), *sprite));
+        transitions
+    }
+}
+
+// Builds the autotile atlas from the tile atlas. It has a row for every registered tile, in id
+// order, followed by an empty row for tiles that aren't registered. Every animation frame has a
+// column per neighbor mask, where the tile's sprite is shaded on the sides facing a different
+// tile, and a column per transition sprite. Animated tiles get a frame per step of their
+// animation with the sprite scrolled sideways, like waves. Uses the same padded layout as the
+// tile atlas.
+pub fn build_autotile_atlas(tile_atlas: &RgbaImage) -> RgbaImage {
+    let slot_size = TILE_SIZE as u32 + 2;
+    let registry = tile_registry();
+    let mut autotile_atlas = RgbaImage::new(
+        slot_size * SPRITES_PER_FRAME as u32 * MAX_ANIMATION_FRAMES as u32,
+        slot_size * (registry.len() as u32 + 1),
+    );
+
+    for tile in registry.iter().map(|definition| &definition.id) {
+        let source = atlas_lookup::tile_rect(tile);
+        let frame_count =
+            tile_animation(tile).map_or(1, |animation| animation.frame_durations.len());
+        for frame in 0..frame_count {
+            let scroll = frame as u32 * TILE_SIZE as u32 / frame_count as u32;
+            for sprite in 0..SPRITES_PER_FRAME {
+                let dest = atlas_lookup::autotile_rect(tile, sprite, frame);
+                // Padding pixels repeat the nearest sprite pixel, so filtering never bleeds in black
+                for pad_y in -1..=TILE_SIZE as i32 {
+                    for pad_x in -1..=TILE_SIZE as i32 {
+                        let sprite_x = pad_x.clamp(0, TILE_SIZE as i32 - 1) as u32;
+                        let sprite_y = pad_y.clamp(0, TILE_SIZE as i32 - 1) as u32;
+                        let pixel = *tile_atlas.get_pixel(
+                            source.x as u32 + (sprite_x + scroll) % TILE_SIZE as u32,
+                            source.y as u32 + sprite_y,
+                        );
+                        let pixel = if sprite < NEIGHBOR_MASKS {
+                            shade_edge_pixel(pixel, sprite_x, sprite_y, sprite)
+                        } else {
+                            fade_transition_pixel(pixel, sprite_x, sprite_y, sprite)
+                        };
+                        autotile_atlas.put_pixel(
+                            (dest.x as i32 + pad_x) as u32,
+                            (dest.y as i32 + pad_y) as u32,
+                            pixel,
+                        );
+                    }
+                }
+            }
+        }
+    }
+    autotile_atlas
+}
+
+// Decodes the embedded tile atlas and builds the autotile atlas from it
+pub fn load_autotile_atlas() -> RgbaImage {
+    let tile_atlas = image::load_from_memory(TILE_ATLAS_PNG)
+        .expect("embedded tile atlas is a valid png")
+        .to_rgba8();
+    build_autotile_atlas(&tile_atlas)
+}
+
+// Distance in pixels from a sprite pixel to each side of the sprite
+fn edge_distances(sprite_x: u32, sprite_y: u32) -> [(u8, u32); 4] {
+    let last = TILE_SIZE as u32 - 1;
+    [
+        (NEIGHBOR_NORTH, sprite_y),
+        (NEIGHBOR_EAST, last - sprite_x),
+        (NEIGHBOR_SOUTH, last - sprite_y),
+        (NEIGHBOR_WEST, sprite_x),
+    ]
+}
+
+fn shade_edge_pixel(pixel: Rgba<u8>, sprite_x: u32, sprite_y: u32, mask: u8) -> Rgba<u8> {
+    let edge_distances = edge_distances(sprite_x, sprite_y);
+    // Only edges facing a different tile are shaded, the closest one decides the shade
+    let Some(distance) = edge_distances
+        .iter()
+        .filter(|(bit, _)| mask & bit == 0)
+        .map(|(_, distance)| *distance as usize)
+        .min()
+    else {
+        return pixel;
+    };
+    let Some(shade) = EDGE_SHADING.get(distance) else {
+        return pixel;
+    };
+    let [r, g, b, a] = pixel.0;
+    let shade_channel = |channel: u8| (channel as f32 * shade).round() as u8;
+    Rgba([shade_channel(r), shade_channel(g), shade_channel(b), a])
+}
+
+// Fades a pixel of a transition sprite out with its distance from the side it spreads in from
+fn fade_transition_pixel(pixel: Rgba<u8>, sprite_x: u32, sprite_y: u32, sprite: u8) -> Rgba<u8> {
+    let side = SIDES[(sprite - NEIGHBOR_MASKS) as usize];
+    let (_, distance) = edge_distances(sprite_x, sprite_y)
+        .into_iter()
+        .find(|(bit, _)| *bit == side)
+        .unwrap();
+    let opacity = TRANSITION_FADE
+        .get(distance as usize)
+        .copied()
+        .unwrap_or(0.0);
+    let [r, g, b, a] = pixel.0;
+    Rgba([r, g, b, (a as f32 * opacity).round() as u8])
+}
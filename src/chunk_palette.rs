@@ -0,0 +1,139 @@
+use std::io::{self, Read, Write};
+
+use crate::world::*;
+
+// Paletted chunk layout (all numbers little endian):
+//   palette length (u16, 1 to 256), the palette's tile ids (u16 each), then the index into the
+//   palette of every tile in the chunk, row by row, packed into bytes lowest bits first. Indices
+//   use 0, 1, 2, 4 or 8 bits depending on the palette length, so a chunk of a single tile is
+//   just 4 bytes.
+const MAX_PALETTE_LENGTH: usize = TILES_PER_CHUNK;
+
+// A chunk's tiles as the distinct tiles in it plus a small index per tile. Used to write chunks
+// to world and region files, and works as well for sending chunks anywhere else.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PalettedTiles {
+    pub palette: Vec<TileId>, // In order of first appearance
+    bits_per_tile: u32,
+    indices: Vec<u8>, // Packed palette indices
+}
+
+impl PalettedTiles {
+    pub fn encode(tiles: &[TileId; TILES_PER_CHUNK]) -> Self {
+        let mut palette: Vec<TileId> = Vec::new();
+        let mut tile_indices = [0; TILES_PER_CHUNK];
+        for (tile, tile_index) in tiles.iter().zip(&mut tile_indices) {
+            *tile_index = match palette.iter().position(|entry| entry == tile) {
+                Some(index) => index,
+                None => {
+                    palette.push(*tile);
+                    palette.len() - 1
+                }
+            };
+        }
+
+        let bits_per_tile = bits_per_tile(palette.len());
+        let mut indices = vec![0; packed_len(bits_per_tile)];
+        if bits_per_tile > 0 {
+            for (tile_position, tile_index) in tile_indices.into_iter().enumerate() {
+                let bit = tile_position * bits_per_tile as usize;
+                indices[bit / 8] |= (tile_index as u8) << (bit % 8);
+            }
+        }
+        PalettedTiles {
+            palette,
+            bits_per_tile,
+            indices,
+        }
+    }
+
+    pub fn decode(&self) -> [TileId; TILES_PER_CHUNK] {
+        std::array::from_fn(|tile_position| self.get(tile_position))
+    }
+
+    // Tile at a local index, without decoding the whole chunk
+    pub fn get(&self, local_index: usize) -> TileId {
+        self.palette[self.palette_index(local_index)]
+    }
+
+    // Bytes written by write_to
+    pub fn encoded_len(&self) -> usize {
+        2 + self.palette.len() * 2 + self.indices.len()
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&(self.palette.len() as u16).to_le_bytes())?;
+        for tile in &self.palette {
+            writer.write_all(&tile.0.to_le_bytes())?;
+        }
+        writer.write_all(&self.indices)
+    }
+
+    // Reads tiles written with write_to. The tile ids aren't checked against the tile registry.
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Self> {
+        let mut bytes = [0; 2];
+        reader.read_exact(&mut bytes)?;
+        let palette_length = u16::from_le_bytes(bytes) as usize;
+        if !(1..=MAX_PALETTE_LENGTH).contains(&palette_length) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("chunk palette has {} tiles", palette_length),
+            ));
+        }
+        let mut palette = Vec::with_capacity(palette_length);
+        for _ in 0..palette_length {
+            reader.read_exact(&mut bytes)?;
+            palette.push(TileId(u16::from_le_bytes(bytes)));
+        }
+
+        let bits_per_tile = bits_per_tile(palette_length);
+        let mut indices = vec![0; packed_len(bits_per_tile)];
+        reader.read_exact(&mut indices)?;
+        let paletted = PalettedTiles {
+            palette,
+            bits_per_tile,
+            indices,
+        };
+        // Lengths that aren't a power of two leave room for indices past the end of the palette
+        if (0..TILES_PER_CHUNK)
+            .any(|local_index| paletted.palette_index(local_index) >= palette_length)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "chunk tile is outside the palette",
+            ));
+        }
+        Ok(paletted)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.encoded_len());
+        self.write_to(&mut bytes)
+            .expect("writing to a vec doesn't fail");
+        bytes
+    }
+
+    fn palette_index(&self, local_index: usize) -> usize {
+        if self.bits_per_tile == 0 {
+            return 0;
+        }
+        let bit = local_index * self.bits_per_tile as usize;
+        let mask = (1u16 << self.bits_per_tile) - 1;
+        ((self.indices[bit / 8] as u16 >> (bit % 8)) & mask) as usize
+    }
+}
+
+// Smallest index size that fits the palette and divides a byte evenly
+fn bits_per_tile(palette_length: usize) -> u32 {
+    match palette_length {
+        0..=1 => 0,
+        2 => 1,
+        3..=4 => 2,
+        5..=16 => 4,
+        _ => 8,
+    }
+}
+
+fn packed_len(bits_per_tile: u32) -> usize {
+    TILES_PER_CHUNK * bits_per_tile as usize / 8
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::world::*;
+use crate::world_generation::PerlinChunkGenerator;
+
+// Generates chunks on a pool of worker threads. Chunks are requested by position and handed
+// back through a results channel that the main loop drains once per frame. Every chunk only
+// depends on the seed and its position, so the order workers finish in doesn't matter.
+pub struct ChunkGenerationPipeline {
+    request_sender: Option<Sender<ChunkPos>>,
+    result_receiver: Receiver<(ChunkPos, Chunk)>,
+    pending: HashSet<ChunkPos>, // Requested chunks that haven't been received yet
+    shutdown: Arc<AtomicBool>, // Set when the pipeline is dropped, workers skip the queued requests
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ChunkGenerationPipeline {
+    pub fn new(generator: Arc<PerlinChunkGenerator>, worker_count: usize) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel::<ChunkPos>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let request_receiver = Arc::new(Mutex::new(request_receiver));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let workers = (0..worker_count.max(1))
+            .map(|_| {
+                let generator = Arc::clone(&generator);
+                let request_receiver = Arc::clone(&request_receiver);
+                let result_sender = result_sender.clone();
+                let shutdown = Arc::clone(&shutdown);
+                thread::spawn(move || loop {
+                    // The lock is only held while waiting for a request, not while generating
+                    let request = request_receiver.lock().unwrap().recv();
+                    // Requests still queued when the pipeline is dropped are received too
+                    let Ok(chunk_pos) = request else {
+                        return;
+                    };
+                    if shutdown.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    let chunk = generator.generate_chunk(&chunk_pos);
+                    if result_sender.send((chunk_pos, chunk)).is_err() {
+                        return;
+                    }
+                })
+            })
+            .collect();
+
+        ChunkGenerationPipeline {
+            request_sender: Some(request_sender),
+            result_receiver,
+            pending: HashSet::new(),
+            shutdown,
+            workers,
+        }
+    }
+
+    // Uses one worker per core, leaving one core for the main loop
+    pub fn with_default_workers(generator: Arc<PerlinChunkGenerator>) -> Self {
+        let cores = thread::available_parallelism().map_or(1, |cores| cores.get());
+        Self::new(generator, cores.saturating_sub(1))
+    }
+
+    // Queues a chunk for generation, chunks that are already queued are ignored
+    pub fn request_chunk(&mut self, chunk_pos: &ChunkPos) {
+        if !self.pending.insert(*chunk_pos) {
+            return;
+        }
+        if let Some(request_sender) = &self.request_sender {
+            let _ = request_sender.send(*chunk_pos);
+        }
+    }
+
+    // Returns every chunk finished since the last call, without blocking
+    pub fn receive_chunks(&mut self) -> Vec<(ChunkPos, Chunk)> {
+        let chunks: Vec<(ChunkPos, Chunk)> = self.result_receiver.try_iter().collect();
+        for (chunk_pos, _) in &chunks {
+            self.pending.remove(chunk_pos);
+        }
+        chunks
+    }
+}
+
+impl Drop for ChunkGenerationPipeline {
+    fn drop(&mut self) {
+        // Workers exit once they finish their current chunk, instead of working through the queue.
+        // Closing the request channel wakes the ones waiting for a request.
+        self.shutdown.store(true, Ordering::Relaxed);
+        self.request_sender = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+// Generating worlds in the background
+impl World {
+    // Like generate_world, but perlin worlds are generated on worker threads. The world starts
+    // out empty and chunks are added by receive_generated_chunks as they finish.
+    pub fn generate_world_in_background(
+        mut self,
+        generation_type: WorldGenerationType,
+        size: WorldGenerationSize,
+        island_size: WorldIslandSize,
+        seed: u32,
+    ) -> Self {
+        let generator = match generation_type {
+            WorldGenerationType::PerlinTerrain => PerlinChunkGenerator::for_finite_world(
+                seed,
+                size as i32,
+                island_size,
+                self.perlin_settings.clone(),
+            ),
+            WorldGenerationType::InfinitePerlinTerrain => {
+                PerlinChunkGenerator::new(seed, island_size, self.perlin_settings.clone())
+            }
+            // The other world types are cheap to generate, and draw from one shared rng
+            _ => return self.generate_world(generation_type, size, island_size, seed),
+        };
+        let generator = Arc::new(generator);
+        let mut pipeline = ChunkGenerationPipeline::with_default_workers(Arc::clone(&generator));
+
+        if generation_type == WorldGenerationType::PerlinTerrain {
+            for chunk_y in 0..size as i32 {
+                for chunk_x in 0..size as i32 {
+                    pipeline.request_chunk(&ChunkPos {
+                        x: chunk_x,
+                        y: chunk_y,
+                    });
+                }
+            }
+        }
+
+        self.chunks.clear();
+        self.seed = seed;
+        self.generation_type = Some(generation_type);
+        self.island_size = Some(island_size);
+        self.chunk_generator = Some(generator);
+        self.chunk_pipeline = Some(pipeline);
+        self
+    }
+
+    // Moves chunks finished by the worker threads into the world, called once per frame
+    pub fn receive_generated_chunks(&mut self) {
+        let Some(pipeline) = &mut self.chunk_pipeline else {
+            return;
+        };
+        for (chunk_pos, chunk) in pipeline.receive_chunks() {
+            // A chunk loaded in the meantime, or saved to disk earlier, may hold edits, so it takes
+            // priority
+            if self.load_chunk(&chunk_pos) {
+                continue;
+            }
+            self.chunks.insert(chunk_pos, chunk);
+            // Generated chunks aren't on disk yet, they have to be written there when evicted
+            self.dirty_chunks.insert(chunk_pos);
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use clap::{Args, Parser, Subcommand};
+use macroquad::prelude::{vec2, Vec2};
+use std::path::PathBuf;
+
+use world_renderer::assets::atlas_lookup::TILE_SIZE;
+use world_renderer::tile_registry::{install_tile_registry, TileRegistry};
+use world_renderer::viewer::camera::{MAX_ZOOM, MIN_ZOOM};
+use world_renderer::viewer::input_map::InputMap;
+use world_renderer::world::*;
+use world_renderer::world_preset::WorldPreset;
+
+// Command line arguments of the renderer
+#[derive(Parser, Debug)]
+#[command(about = "Generates, renders and edits tile worlds")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// World preset to generate, for example presets/archipelago.toml
+    #[arg(long, value_name = "PATH", global = true)]
+    pub preset: Option<PathBuf>,
+
+    /// World file to load instead of generating a new world
+    #[arg(
+        long,
+        value_name = "PATH",
+        global = true,
+        conflicts_with_all = ["preset", "generation_type", "size", "island_size", "seed"]
+    )]
+    pub world: Option<PathBuf>,
+
+    /// Generation type, overrides the preset. One of WaterWorld, ChunkMess, TileMess,
+    /// PerlinTerrain or InfinitePerlinTerrain
+    #[arg(long, short = 't', value_name = "TYPE", global = true)]
+    pub generation_type: Option<WorldGenerationType>,
+
+    /// World size in chunks, overrides the preset. One of Tiny, Small, Medium, Large, Huge or Titanic
+    #[arg(long, short = 's', global = true)]
+    pub size: Option<WorldGenerationSize>,
+
+    /// Island size, overrides the preset. One of Tiny, Small, Medium, Large, Huge or Titanic
+    #[arg(long, short = 'i', global = true)]
+    pub island_size: Option<WorldIslandSize>,
+
+    /// Generation seed, overrides the preset
+    #[arg(long, global = true)]
+    pub seed: Option<u32>,
+
+    /// Tile definitions file adding tiles to the builtin ones, can be given more than once
+    #[arg(long, value_name = "PATH", global = true)]
+    pub tiles: Vec<PathBuf>,
+
+    /// Run in a window instead of fullscreen
+    #[arg(long)]
+    pub windowed: bool,
+
+    /// Key bindings file replacing the default bindings of the actions it lists, see
+    /// src/assets/bindings.toml
+    #[arg(long, value_name = "PATH")]
+    pub bindings: Option<PathBuf>,
+
+    /// Tile the camera starts centered on, as X Y
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["X", "Y"],
+        allow_negative_numbers = true,
+        default_values_t = [0, 0]
+    )]
+    pub camera: Vec<i32>,
+
+    /// Initial zoom, from 1 (widest view) to 16 (closest)
+    #[arg(long, default_value_t = 8.0, value_parser = parse_zoom)]
+    pub zoom: f32,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Renders the world into a png without opening a window
+    Export(ExportArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ExportArgs {
+    /// Png file to write
+    #[arg(long, short = 'o', value_name = "PATH", default_value = "map.png")]
+    pub output: PathBuf,
+
+    /// Tile area to export, inclusive. Defaults to the whole world, infinite worlds need an area
+    #[arg(
+        long,
+        num_args = 4,
+        value_names = ["MIN_X", "MIN_Y", "MAX_X", "MAX_Y"],
+        allow_negative_numbers = true
+    )]
+    pub area: Option<Vec<i32>>,
+
+    /// Draw every tile as one pixel of a flat color instead of its sprite
+    #[arg(long)]
+    pub colors: bool,
+}
+
+fn parse_zoom(text: &str) -> Result<f32, String> {
+    let zoom: f32 = text
+        .parse()
+        .map_err(|_| format!("\"{}\" is not a number", text))?;
+    if !(MIN_ZOOM..=MAX_ZOOM).contains(&zoom) {
+        return Err(format!(
+            "zoom must be between {} and {}",
+            MIN_ZOOM, MAX_ZOOM
+        ));
+    }
+    Ok(zoom)
+}
+
+impl Cli {
+    // Installs the builtin tiles together with the ones from --tiles. Has to happen before worlds
+    // or presets are loaded, they refer to tiles by id and name.
+    pub fn install_tiles(&self) -> Result<(), String> {
+        let mut registry = TileRegistry::builtin();
+        for tiles_path in &self.tiles {
+            registry
+                .load(tiles_path)
+                .map_err(|err| format!("Failed to load tiles {}: {}", tiles_path.display(), err))?;
+        }
+        install_tile_registry(registry)
+    }
+
+    // Default key bindings, with those of the bindings file if one was given
+    pub fn input_map(&self) -> Result<InputMap, String> {
+        let mut input_map = InputMap::builtin();
+        if let Some(bindings_path) = &self.bindings {
+            input_map.load(bindings_path).map_err(|err| {
+                format!(
+                    "Failed to load bindings {}: {}",
+                    bindings_path.display(),
+                    err
+                )
+            })?;
+        }
+        Ok(input_map)
+    }
+
+    // Loads the world file if one was given
+    pub fn load_world(&self) -> Option<Result<World, String>> {
+        let world_path = self.world.as_ref()?;
+        Some(
+            World::load(world_path)
+                .map_err(|err| format!("Failed to load world {}: {}", world_path.display(), err)),
+        )
+    }
+
+    // The preset to generate, with the command line overrides applied
+    pub fn world_preset(&self) -> Result<WorldPreset, String> {
+        let mut preset = match &self.preset {
+            Some(preset_path) => WorldPreset::load(preset_path).map_err(|err| {
+                format!("Failed to load preset {}: {}", preset_path.display(), err)
+            })?,
+            None => WorldPreset::default(),
+        };
+        if let Some(generation_type) = self.generation_type {
+            preset.generation_type = generation_type;
+        }
+        if let Some(size) = self.size {
+            preset.size = size;
+        }
+        if let Some(island_size) = self.island_size {
+            preset.island_size = island_size;
+        }
+        if let Some(seed) = self.seed {
+            preset.seed = seed;
+        }
+        Ok(preset)
+    }
+
+    // Camera target centered on the starting tile
+    pub fn camera_target(&self) -> Vec2 {
+        vec2(
+            (self.camera[0] as f32 + 0.5) * TILE_SIZE,
+            -(self.camera[1] as f32 + 0.5) * TILE_SIZE,
+        )
+    }
+}
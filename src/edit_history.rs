@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+
+use crate::tile_metadata::TileMetadata;
+use crate::world::*;
+
+// A single tile change, with what it takes to revert it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileEdit {
+    pub pos: GlobalTilePos,
+    pub layer: TileLayer,
+    pub old_tile: Option<TileId>,
+    pub new_tile: Option<TileId>,
+    pub old_metadata: Option<TileMetadata>, // Dropped when the tile was replaced, restored by undo
+}
+
+// Edits that are undone and redone together, like one stroke of the brush
+pub type EditAction = Vec<TileEdit>;
+
+// Records tile edits so they can be undone and redone. Every edit goes through apply, edits made
+// between begin_stroke and end_stroke are grouped into one action. Only the last max_actions
+// actions are kept.
+#[derive(Debug, Clone)]
+pub struct EditHistory {
+    undo_actions: VecDeque<EditAction>,
+    redo_actions: Vec<EditAction>,
+    stroke: Option<EditAction>, // Edits of the stroke in progress
+    max_actions: usize,
+}
+
+impl EditHistory {
+    pub fn new(max_actions: usize) -> Self {
+        EditHistory {
+            undo_actions: VecDeque::new(),
+            redo_actions: Vec::new(),
+            stroke: None,
+            max_actions,
+        }
+    }
+
+    // Starts grouping edits, does nothing if a stroke is already in progress
+    pub fn begin_stroke(&mut self) {
+        self.stroke.get_or_insert_with(Vec::new);
+    }
+
+    // Ends the stroke in progress, making its edits one action
+    pub fn end_stroke(&mut self) {
+        if let Some(stroke) = self.stroke.take() {
+            self.push_action(stroke);
+        }
+    }
+
+    pub fn in_stroke(&self) -> bool {
+        self.stroke.is_some()
+    }
+
+    // Sets or clears a tile like World::set_layer_tile and records the edit. Edits that don't
+    // change anything aren't recorded. Returns false if the tile couldn't be set.
+    pub fn apply(
+        &mut self,
+        world: &mut World,
+        pos: &GlobalTilePos,
+        layer: TileLayer,
+        tile: Option<TileId>,
+    ) -> bool {
+        let old_tile = world.get_layer_tile(pos, layer);
+        let old_metadata = world.get_tile_metadata(pos, layer).cloned();
+        if !world.set_layer_tile(pos, layer, tile) {
+            return false;
+        }
+        if old_tile == tile {
+            return true;
+        }
+        let edit = TileEdit {
+            pos: *pos,
+            layer,
+            old_tile,
+            new_tile: tile,
+            old_metadata,
+        };
+        match &mut self.stroke {
+            Some(stroke) => stroke.push(edit),
+            None => self.push_action(vec![edit]),
+        }
+        true
+    }
+
+    // Reverts the last action, ending the stroke in progress first. Returns false if there was
+    // nothing to undo.
+    pub fn undo(&mut self, world: &mut World) -> bool {
+        self.end_stroke();
+        let Some(action) = self.undo_actions.pop_back() else {
+            return false;
+        };
+        // Later edits may have been made on top of earlier ones, so they are reverted first
+        for edit in action.iter().rev() {
+            world.set_layer_tile(&edit.pos, edit.layer, edit.old_tile);
+            if let Some(metadata) = &edit.old_metadata {
+                world.set_tile_metadata(&edit.pos, edit.layer, metadata.clone());
+            }
+        }
+        self.redo_actions.push(action);
+        true
+    }
+
+    // Applies the last undone action again. Returns false if there was nothing to redo.
+    pub fn redo(&mut self, world: &mut World) -> bool {
+        self.end_stroke();
+        let Some(action) = self.redo_actions.pop() else {
+            return false;
+        };
+        for edit in &action {
+            world.set_layer_tile(&edit.pos, edit.layer, edit.new_tile);
+        }
+        self.undo_actions.push_back(action);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_actions.is_empty()
+            || self
+                .stroke
+                .as_ref()
+                .is_some_and(|stroke| !stroke.is_empty())
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_actions.is_empty()
+    }
+
+    // Forgets every action, for when the world is replaced
+    pub fn clear(&mut self) {
+        self.undo_actions.clear();
+        self.redo_actions.clear();
+        self.stroke = None;
+    }
+
+    // Actions that can be undone, not counting the stroke in progress
+    pub fn len(&self) -> usize {
+        self.undo_actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.undo_actions.is_empty()
+    }
+
+    fn push_action(&mut self, action: EditAction) {
+        if action.is_empty() {
+            return;
+        }
+        // A new action makes the undone ones unreachable
+        self.redo_actions.clear();
+        self.undo_actions.push_back(action);
+        while self.undo_actions.len() > self.max_actions {
+            self.undo_actions.pop_front();
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::world::*;
+
+// Tools of the tile editor. Each tool only works out which tiles it affects, applying the edits is
+// left to the caller, so the shapes can be tested without a window.
+
+pub const MAX_FLOOD_FILL_TILES: usize = 64 * 64 * 16; // Larger regions are refused, like open ocean in infinite worlds
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrushShape {
+    Circle,
+    Square,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditorTool {
+    Brush(BrushShape), // Paints while dragged
+    Line,
+    Rectangle { filled: bool },
+    Ellipse { filled: bool },
+    FloodFill,
+    Eyedropper, // Picks the tile to paint with
+    Select,     // Drags out the area to copy
+    Paste,      // Places the copied stamp
+}
+
+impl EditorTool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EditorTool::Brush(BrushShape::Circle) => "Circle brush",
+            EditorTool::Brush(BrushShape::Square) => "Square brush",
+            EditorTool::Line => "Line",
+            EditorTool::Rectangle { filled: false } => "Rectangle",
+            EditorTool::Rectangle { filled: true } => "Filled rectangle",
+            EditorTool::Ellipse { filled: false } => "Ellipse",
+            EditorTool::Ellipse { filled: true } => "Filled ellipse",
+            EditorTool::FloodFill => "Flood fill",
+            EditorTool::Eyedropper => "Eyedropper",
+            EditorTool::Select => "Select",
+            EditorTool::Paste => "Paste",
+        }
+    }
+}
+
+// Tiles covered by a brush centered on a tile. A radius of 0 is just the center tile.
+pub fn brush(shape: BrushShape, center: &GlobalTilePos, radius: u32) -> Vec<GlobalTilePos> {
+    let radius = radius as i32;
+    let mut tiles = Vec::new();
+    for y in -radius..=radius {
+        for x in -radius..=radius {
+            // Allowing up to r * (r + 1) instead of r * r rounds off the flat sides of small circles
+            let inside = match shape {
+                BrushShape::Circle => x * x + y * y <= radius * (radius + 1),
+                BrushShape::Square => true,
+            };
+            if inside {
+                tiles.push(GlobalTilePos(center.0 + x, center.1 + y));
+            }
+        }
+    }
+    tiles
+}
+
+// Tiles covered by a brush dragged from start to end, so fast mouse moves don't leave gaps
+pub fn brush_stroke(
+    shape: BrushShape,
+    start: &GlobalTilePos,
+    end: &GlobalTilePos,
+    radius: u32,
+) -> Vec<GlobalTilePos> {
+    let mut seen = HashSet::new();
+    line(start, end)
+        .iter()
+        .flat_map(|center| brush(shape, center, radius))
+        .filter(|pos| seen.insert(*pos))
+        .collect()
+}
+
+// Tiles on a line from start to end, both included, without diagonal gaps being doubled up
+pub fn line(start: &GlobalTilePos, end: &GlobalTilePos) -> Vec<GlobalTilePos> {
+    // Bresenham's line algorithm
+    let (dx, dy) = ((end.0 - start.0).abs(), -(end.1 - start.1).abs());
+    let (step_x, step_y) = ((end.0 - start.0).signum(), (end.1 - start.1).signum());
+    let mut error = dx + dy;
+    let mut pos = *start;
+    let mut tiles = vec![pos];
+    while pos != *end {
+        let doubled_error = 2 * error;
+        if doubled_error >= dy {
+            error += dy;
+            pos.0 += step_x;
+        }
+        if doubled_error <= dx {
+            error += dx;
+            pos.1 += step_y;
+        }
+        tiles.push(pos);
+    }
+    tiles
+}
+
+// Tiles of the rectangle with two opposite corners, just the border unless filled
+pub fn rectangle(
+    corner: &GlobalTilePos,
+    opposite_corner: &GlobalTilePos,
+    filled: bool,
+) -> Vec<GlobalTilePos> {
+    let (min, max) = bounds(corner, opposite_corner);
+    let mut tiles = Vec::new();
+    for y in min.1..=max.1 {
+        for x in min.0..=max.0 {
+            let on_border = x == min.0 || x == max.0 || y == min.1 || y == max.1;
+            if filled || on_border {
+                tiles.push(GlobalTilePos(x, y));
+            }
+        }
+    }
+    tiles
+}
+
+// Tiles of the ellipse fitting in the rectangle with two opposite corners, just the outline unless
+// filled
+pub fn ellipse(
+    corner: &GlobalTilePos,
+    opposite_corner: &GlobalTilePos,
+    filled: bool,
+) -> Vec<GlobalTilePos> {
+    let (min, max) = bounds(corner, opposite_corner);
+    let center = (
+        (min.0 + max.0 + 1) as f64 / 2.0,
+        (min.1 + max.1 + 1) as f64 / 2.0,
+    );
+    let radii = (
+        (max.0 - min.0 + 1) as f64 / 2.0,
+        (max.1 - min.1 + 1) as f64 / 2.0,
+    );
+    // A tile is inside when its center is
+    let inside = |x: i32, y: i32| {
+        let normalized_x = (x as f64 + 0.5 - center.0) / radii.0;
+        let normalized_y = (y as f64 + 0.5 - center.1) / radii.1;
+        normalized_x * normalized_x + normalized_y * normalized_y <= 1.0
+    };
+    let mut tiles = Vec::new();
+    for y in min.1..=max.1 {
+        for x in min.0..=max.0 {
+            if !inside(x, y) {
+                continue;
+            }
+            let on_outline = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+                .iter()
+                .any(|(step_x, step_y)| !inside(x + step_x, y + step_y));
+            if filled || on_outline {
+                tiles.push(GlobalTilePos(x, y));
+            }
+        }
+    }
+    tiles
+}
+
+// Tiles connected to start (not diagonally) that have the same tile as start in the layer, only
+// going through loaded chunks. In overlay layers an empty start fills the empty area around it.
+// Returns None if the area has more than max_tiles tiles.
+pub fn flood_fill(
+    world: &World,
+    start: &GlobalTilePos,
+    layer: TileLayer,
+    max_tiles: usize,
+) -> Option<Vec<GlobalTilePos>> {
+    world.loaded_tile(start)?;
+    let target = world.get_layer_tile(start, layer);
+    let mut tiles = Vec::new();
+    let mut seen = HashSet::from([*start]);
+    let mut queue = VecDeque::from([*start]);
+    while let Some(pos) = queue.pop_front() {
+        tiles.push(pos);
+        if tiles.len() > max_tiles {
+            return None;
+        }
+        for (step_x, step_y) in [(0, -1), (1, 0), (0, 1), (-1, 0)] {
+            let neighbor = GlobalTilePos(pos.0 + step_x, pos.1 + step_y);
+            let loaded = world.loaded_tile(&neighbor).is_some();
+            if loaded && world.get_layer_tile(&neighbor, layer) == target && seen.insert(neighbor) {
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    Some(tiles)
+}
+
+// The tile the eyedropper picks up at a position, the uppermost one
+pub fn pick_tile(world: &World, pos: &GlobalTilePos) -> Option<TileId> {
+    world.get_top_tile(pos)
+}
+
+// Smallest and largest corner of the rectangle with two opposite corners
+fn bounds(
+    corner: &GlobalTilePos,
+    opposite_corner: &GlobalTilePos,
+) -> (GlobalTilePos, GlobalTilePos) {
+    (
+        GlobalTilePos(
+            corner.0.min(opposite_corner.0),
+            corner.1.min(opposite_corner.1),
+        ),
+        GlobalTilePos(
+            corner.0.max(opposite_corner.0),
+            corner.1.max(opposite_corner.1),
+        ),
+    )
+}
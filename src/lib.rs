@@ -0,0 +1,23 @@
+// World model, generation and saving for tile worlds. Nothing here depends on a renderer, the
+// macroquad viewer lives in the viewer module behind the "viewer" feature.
+pub mod animation;
+pub mod assets;
+pub mod autotile;
+pub mod chunk_palette;
+pub mod chunk_pipeline;
+pub mod edit_history;
+pub mod editor_tools;
+pub mod map_export;
+pub mod region;
+pub mod stamp;
+pub mod tile_metadata;
+pub mod tile_registry;
+pub mod utils;
+#[cfg(feature = "viewer")]
+pub mod viewer;
+pub mod world;
+pub mod world_generation;
+pub mod world_preset;
+pub mod world_saving;
+
+pub use world::World;
@@ -1,40 +1,169 @@
-use assets::*;
+use std::path::Path;
+
+use clap::Parser;
+use cli::{Cli, Command, ExportArgs};
 use macroquad::prelude::*;
-use utils::*;
-use world::*;
+use world_renderer::edit_history::EditHistory;
+use world_renderer::map_export::{map_size, render_map, MapStyle};
+use world_renderer::viewer::assets::AssetHandle;
+use world_renderer::viewer::camera::*;
+use world_renderer::viewer::editor::TileEditor;
+use world_renderer::viewer::editor_panel::EditorPanel;
+use world_renderer::viewer::input::*;
+use world_renderer::viewer::lod::{lod_weight, ChunkLodCache};
+use world_renderer::viewer::rendering::{reserve_chunk_mesh_capacity, ChunkMeshCache};
+use world_renderer::world::*;
+
+mod cli;
 
-mod assets;
-mod utils;
-mod world;
-mod world_generation;
+fn main() {
+    let cli = Cli::parse();
+    if let Err(err) = cli.install_tiles() {
+        exit_with_error(&err);
+    }
+    match &cli.command {
+        Some(Command::Export(export_args)) => export_map(&cli, export_args),
+        None => {
+            let conf = Conf {
+                window_title: "Rendering tests".to_string(),
+                fullscreen: !cli.windowed,
+                ..Default::default()
+            };
+            macroquad::Window::from_config(conf, run_viewer(cli));
+        }
+    }
+}
+
+// Writes the world to a png, runs without a window
+fn export_map(cli: &Cli, export_args: &ExportArgs) {
+    let mut world = match cli.load_world() {
+        Some(world) => world.unwrap_or_else(|err| exit_with_error(&err)),
+        None => {
+            let preset = cli
+                .world_preset()
+                .unwrap_or_else(|err| exit_with_error(&err));
+            println!("Generating world \"{}\"", preset.name);
+            preset.generate_blocking()
+        }
+    };
+
+    let (min, max) = match &export_args.area {
+        Some(area) => (
+            GlobalTilePos(area[0], area[1]),
+            GlobalTilePos(area[2], area[3]),
+        ),
+        None if world.chunk_generator.is_some() => {
+            exit_with_error("Infinite worlds need an --area to export")
+        }
+        None => world
+            .loaded_tile_bounds()
+            .unwrap_or_else(|| exit_with_error("The world is empty, nothing to export")),
+    };
+    if min.0 > max.0 || min.1 > max.1 {
+        exit_with_error("The export area's min corner must be above and left of its max corner");
+    }
+    let style = if export_args.colors {
+        MapStyle::Colors
+    } else {
+        MapStyle::Sprites
+    };
+    // Checked before loading anything, huge areas would take ages to generate
+    if let Err(err) = map_size(&min, &max, style) {
+        exit_with_error(&format!("Can't export the area: {}", err));
+    }
+    world.load_area(&min, &max);
 
-#[macroquad::main("Rendering tests")]
-async fn main() {
+    let map = render_map(&world, &min, &max, style).unwrap_or_else(|err| exit_with_error(&err));
+    if let Err(err) = map.save(&export_args.output) {
+        exit_with_error(&format!(
+            "Failed to write {}: {}",
+            export_args.output.display(),
+            err
+        ));
+    }
+    println!(
+        "Exported tiles {:?} to {:?} into {}",
+        min,
+        max,
+        export_args.output.display()
+    );
+}
+
+async fn run_viewer(cli: Cli) {
     // Initilizing game
     let asset_handle: AssetHandle = AssetHandle::new();
+    reserve_chunk_mesh_capacity();
+    let mut chunk_meshes = ChunkMeshCache::new();
+    let mut chunk_lods = ChunkLodCache::new();
+    let mut edit_history = EditHistory::new(EDIT_HISTORY_SIZE);
+    let mut tile_editor = TileEditor::new();
+    let mut editor_panel = EditorPanel::new();
     let mut camera = make_camera();
-    let mut world = World::new().generate_world(
-        WorldGenerationType::PerlinTerrain,
-        WorldGenerationSize::Large,
-        WorldIslandSize::Large,
-        25,
-    );
+    camera.target = cli.camera_target();
+    let input_map = cli.input_map().unwrap_or_else(|err| exit_with_error(&err));
 
-    set_fullscreen(true);
+    let mut region_directory = None;
+    let mut world = match cli.load_world() {
+        Some(world) => world.unwrap_or_else(|err| exit_with_error(&err)),
+        None => {
+            let preset = cli
+                .world_preset()
+                .unwrap_or_else(|err| exit_with_error(&err));
+            println!("Generating world \"{}\"", preset.name);
+            // Titanic worlds are too big to keep in memory. Every world gets its own directory,
+            // so region files of another world are never mixed in.
+            if preset.size == WorldGenerationSize::Titanic {
+                region_directory = Some(Path::new(REGION_DIRECTORY).join(preset.storage_name()));
+            }
+            preset.generate()
+        }
+    };
+    if let Some(region_directory) = region_directory {
+        if let Err(err) = world.use_region_storage(&region_directory) {
+            eprintln!(
+                "Failed to create region files in {}: {}",
+                region_directory.display(),
+                err
+            );
+        }
+    }
 
-    let mut camera_zoom_offset = 8.0;
+    let mut camera_controls = CameraControls::new(cli.zoom);
 
     // Main Game loop
     loop {
         // Update game
-        camera.zoom = vec2(1.0 / screen_width(), 1.0 / screen_height());
-        camera.zoom *= camera_zoom_offset;
-        handle_camera_controls(&mut camera, &mut camera_zoom_offset);
-        handle_camera_tile_edits(&camera, &mut world);
+        let actions = input_map.actions(&input_map.capture());
+        camera_controls.handle_input(
+            &mut camera,
+            &actions,
+            mouse_position().into(),
+            vec2(screen_width(), screen_height()),
+            get_frame_time(),
+        );
+        editor_panel.handle_input(&actions);
+        tile_editor.handle_input(
+            &actions,
+            mouse_tile_pos(&camera),
+            &mut world,
+            &mut edit_history,
+            editor_panel.contains_mouse(),
+        );
+        world.receive_generated_chunks();
+        world.update_loaded_chunks(&camera_view_rect(&camera));
+        handle_world_saving(&mut world, &mut edit_history, &actions);
 
         // Render in world space
         set_camera(&camera);
-        world.render_visible_tiles(&camera, &asset_handle);
+        let lod_weight = lod_weight(&camera);
+        if lod_weight < 1.0 {
+            world.render_visible_chunks(&camera, &asset_handle, &mut chunk_meshes);
+        }
+        if lod_weight > 0.0 {
+            // Fades in over the sprites as the camera zooms out
+            world.render_visible_chunk_lods(&camera, &mut chunk_lods, lod_weight);
+        }
+        tile_editor.draw_preview(&camera);
 
         // Render in ui space
         set_default_camera(); // Sets camera to default camera, used for ui rendering.
@@ -45,6 +174,19 @@ async fn main() {
             50.0,
             WHITE,
         ); // Draws fps
+        editor_panel.draw(
+            &mut tile_editor,
+            &world,
+            &camera,
+            &asset_handle,
+            &actions,
+            &input_map,
+        );
         next_frame().await;
     }
 }
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
@@ -0,0 +1,145 @@
+use image::{Pixel, Rgba, RgbaImage};
+
+use crate::assets::atlas_lookup::{self, TILE_SIZE};
+use crate::autotile::{load_autotile_atlas, NEIGHBOR_ALL};
+use crate::world::*;
+
+// How tiles are drawn into an exported map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapStyle {
+    Sprites, // Every tile is drawn with its autotile sprite, like in the viewer
+    Colors,  // Every tile is a single pixel of its map color
+}
+
+pub const MAX_MAP_SIZE: u32 = 16384; // Width and height limit of exported images in pixels
+
+// Size in tiles of the map of the area between min and max (inclusive), failing if the image
+// would be larger than MAX_MAP_SIZE on either side
+pub fn map_size(
+    min: &GlobalTilePos,
+    max: &GlobalTilePos,
+    style: MapStyle,
+) -> Result<(u32, u32), String> {
+    let pixels_per_tile = match style {
+        MapStyle::Sprites => TILE_SIZE as i64,
+        MapStyle::Colors => 1,
+    };
+    let width = (max.0 as i64 - min.0 as i64 + 1).max(0);
+    let height = (max.1 as i64 - min.1 as i64 + 1).max(0);
+    if width * pixels_per_tile > MAX_MAP_SIZE as i64
+        || height * pixels_per_tile > MAX_MAP_SIZE as i64
+    {
+        return Err(format!(
+            "the area is {}x{} tiles, too large for a {}x{} pixel image",
+            width, height, MAX_MAP_SIZE, MAX_MAP_SIZE
+        ));
+    }
+    Ok((width as u32, height as u32))
+}
+
+// Renders every tile between min and max (inclusive) into an image on the cpu, without needing a
+// window or gpu. Rows go down the image as the tile y grows, like on screen. Missing tiles are
+// left transparent. Colors maps show the uppermost layer of every tile, sprite maps draw the
+// layers over each other. Fails for areas too large to export, see map_size.
+pub fn render_map(
+    world: &World,
+    min: &GlobalTilePos,
+    max: &GlobalTilePos,
+    style: MapStyle,
+) -> Result<RgbaImage, String> {
+    let (width, height) = map_size(min, max, style)?;
+    let map = match style {
+        MapStyle::Colors => {
+            let mut map = RgbaImage::new(width, height);
+            for (x, y, pixel) in map.enumerate_pixels_mut() {
+                let pos = GlobalTilePos(min.0 + x as i32, min.1 + y as i32);
+                if let Some(tile) = world.get_top_tile(&pos) {
+                    *pixel = Rgba(tile.map_color());
+                }
+            }
+            map
+        }
+        MapStyle::Sprites => {
+            let atlas = load_autotile_atlas();
+            let tile_size = TILE_SIZE as u32;
+            let mut map = RgbaImage::new(width * tile_size, height * tile_size);
+            for y in 0..height {
+                for x in 0..width {
+                    let pos = GlobalTilePos(min.0 + x as i32, min.1 + y as i32);
+                    let Some(tile) = world.loaded_tile(&pos) else {
+                        continue;
+                    };
+                    let source = atlas_lookup::autotile_rect(tile, world.neighbor_mask(&pos), 0);
+                    for sprite_y in 0..tile_size {
+                        for sprite_x in 0..tile_size {
+                            let pixel = atlas
+                                .get_pixel(source.x as u32 + sprite_x, source.y as u32 + sprite_y);
+                            map.put_pixel(
+                                x * tile_size + sprite_x,
+                                y * tile_size + sprite_y,
+                                *pixel,
+                            );
+                        }
+                    }
+                    // Transitions from neighbors, then decorations and objects, are blended over
+                    // the terrain
+                    let transitions = world.transitions(&pos).into_iter();
+                    let overlays = TileLayer::OVERLAYS.into_iter().filter_map(|layer| {
+                        let tile = world.get_layer_tile(&pos, layer)?;
+                        Some((NEIGHBOR_ALL, tile))
+                    });
+                    for (sprite, tile) in transitions.chain(overlays) {
+                        let source = atlas_lookup::autotile_rect(&tile, sprite, 0);
+                        for sprite_y in 0..tile_size {
+                            for sprite_x in 0..tile_size {
+                                let pixel = atlas.get_pixel(
+                                    source.x as u32 + sprite_x,
+                                    source.y as u32 + sprite_y,
+                                );
+                                map.get_pixel_mut(
+                                    x * tile_size + sprite_x,
+                                    y * tile_size + sprite_y,
+                                )
+                                .blend(pixel);
+                            }
+                        }
+                    }
+                }
+            }
+            map
+        }
+    };
+    Ok(map)
+}
+
+// Map exporting helpers
+impl World {
+    // Returns the smallest tile area containing every chunk in memory, None for empty worlds
+    pub fn loaded_tile_bounds(&self) -> Option<(GlobalTilePos, GlobalTilePos)> {
+        let min_x = self.chunks.keys().map(|chunk_pos| chunk_pos.x).min()?;
+        let min_y = self.chunks.keys().map(|chunk_pos| chunk_pos.y).min()?;
+        let max_x = self.chunks.keys().map(|chunk_pos| chunk_pos.x).max()?;
+        let max_y = self.chunks.keys().map(|chunk_pos| chunk_pos.y).max()?;
+        let last_tile = (CHUNK_SIZE * CHUNK_SIZE - 1) as usize;
+        Some((
+            ChunkPos { x: min_x, y: min_y }.tile_pos(0),
+            ChunkPos { x: max_x, y: max_y }.tile_pos(last_tile),
+        ))
+    }
+
+    // Loads or generates every chunk overlapping the area between min and max (inclusive)
+    pub fn load_area(&mut self, min: &GlobalTilePos, max: &GlobalTilePos) {
+        let (min_chunk, max_chunk) = (min.chunk_pos(), max.chunk_pos());
+        for chunk_y in min_chunk.y..=max_chunk.y {
+            for chunk_x in min_chunk.x..=max_chunk.x {
+                let chunk_pos = ChunkPos {
+                    x: chunk_x,
+                    y: chunk_y,
+                };
+                if !self.load_chunk(&chunk_pos) {
+                    self.generate_chunk(&chunk_pos);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,306 @@
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::world::*;
+use crate::world_saving::{read_chunk, write_chunk};
+
+// Region file layout (all numbers little endian):
+//   magic "WREG", format version (u16), then an offset table with one (offset u32, length u32)
+//   entry per chunk in the region, followed by the chunk data. A length of 0 means the chunk
+//   has never been saved. Chunks are stored like in world files, see write_chunk.
+const REGION_FILE_MAGIC: &[u8; 4] = b"WREG";
+const REGION_FILE_VERSION: u16 = 1;
+const REGION_HEADER_SIZE: u64 = 4 + 2 + CHUNKS_PER_REGION as u64 * 8;
+
+pub const REGION_SIZE: i32 = 32; // Width and height of a region in chunks
+const CHUNKS_PER_REGION: usize = (REGION_SIZE * REGION_SIZE) as usize;
+
+const CHUNK_LOAD_MARGIN: i32 = 1; // Chunks around the view that are kept loaded
+const CHUNK_EVICT_DISTANCE: i32 = 4; // Chunks further than this from the view are written back to disk
+
+type OffsetTable = Vec<(u32, u32)>; // Offset and length of every chunk in a region file
+
+// A directory of region files, each holding REGION_SIZE x REGION_SIZE chunks
+pub struct RegionStorage {
+    directory: PathBuf,
+    // Offset tables of the regions looked at so far, so chunks that were never saved aren't
+    // looked up on disk every time. Only valid while nothing else writes to the directory.
+    offset_tables: RefCell<HashMap<(i32, i32), OffsetTable>>,
+}
+
+impl RegionStorage {
+    pub fn new(directory: impl AsRef<Path>) -> io::Result<Self> {
+        fs::create_dir_all(&directory)?;
+        Ok(RegionStorage {
+            directory: directory.as_ref().to_path_buf(),
+            offset_tables: RefCell::default(),
+        })
+    }
+
+    // Reads a chunk from its region file, returns None if it was never saved
+    pub fn load_chunk(&self, chunk_pos: &ChunkPos) -> io::Result<Option<Chunk>> {
+        let (offset, length) = self.offset_table_entry(chunk_pos)?;
+        if length == 0 {
+            return Ok(None);
+        }
+        let mut reader = BufReader::new(File::open(self.region_path(chunk_pos))?);
+        reader.seek(SeekFrom::Start(offset as u64))?;
+        let mut chunk_bytes = vec![0; length as usize];
+        reader.read_exact(&mut chunk_bytes)?;
+        Ok(Some(read_chunk(&mut chunk_bytes.as_slice())?))
+    }
+
+    // Writes chunks to their region files, keeping every other chunk already stored in them
+    pub fn save_chunks<'a>(
+        &self,
+        chunks: impl IntoIterator<Item = (&'a ChunkPos, &'a Chunk)>,
+    ) -> io::Result<()> {
+        let mut regions: HashMap<(i32, i32), Vec<(&ChunkPos, &Chunk)>> = HashMap::new();
+        for (chunk_pos, chunk) in chunks {
+            regions
+                .entry(region_of(chunk_pos))
+                .or_default()
+                .push((chunk_pos, chunk));
+        }
+
+        for region_chunks in regions.values() {
+            let path = self.region_path(region_chunks[0].0);
+            let mut chunk_data = self.read_region_chunk_data(&path)?;
+            for (chunk_pos, chunk) in region_chunks {
+                let mut chunk_bytes = Vec::new();
+                write_chunk(&mut chunk_bytes, chunk)?;
+                chunk_data[index_in_region(chunk_pos)] = Some(chunk_bytes);
+            }
+            let offsets = Self::write_region(&path, &chunk_data)?;
+            self.offset_tables
+                .borrow_mut()
+                .insert(region_of(region_chunks[0].0), offsets);
+        }
+        Ok(())
+    }
+
+    // Where a chunk is in its region file, from the cached offset table. Regions without a file
+    // have no chunks.
+    fn offset_table_entry(&self, chunk_pos: &ChunkPos) -> io::Result<(u32, u32)> {
+        let region = region_of(chunk_pos);
+        let mut offset_tables = self.offset_tables.borrow_mut();
+        let offsets = match offset_tables.entry(region) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(match File::open(self.region_path(chunk_pos)) {
+                Ok(file) => read_offset_table(&mut BufReader::new(file))?,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    vec![(0, 0); CHUNKS_PER_REGION]
+                }
+                Err(err) => return Err(err),
+            }),
+        };
+        Ok(offsets[index_in_region(chunk_pos)])
+    }
+
+    fn region_path(&self, chunk_pos: &ChunkPos) -> PathBuf {
+        let (region_x, region_y) = region_of(chunk_pos);
+        self.directory
+            .join(format!("r.{}.{}.region", region_x, region_y))
+    }
+
+    // Reads the raw data of every chunk stored in a region file
+    fn read_region_chunk_data(&self, path: &Path) -> io::Result<Vec<Option<Vec<u8>>>> {
+        let mut chunk_data = vec![None; CHUNKS_PER_REGION];
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(chunk_data),
+            Err(err) => return Err(err),
+        };
+        let mut reader = BufReader::new(file);
+        let offsets = read_offset_table(&mut reader)?;
+        for (index, (offset, length)) in offsets.into_iter().enumerate() {
+            if length == 0 {
+                continue;
+            }
+            reader.seek(SeekFrom::Start(offset as u64))?;
+            let mut chunk_bytes = vec![0; length as usize];
+            reader.read_exact(&mut chunk_bytes)?;
+            chunk_data[index] = Some(chunk_bytes);
+        }
+        Ok(chunk_data)
+    }
+
+    // Writes a whole region file, going through a temporary file so a crash never leaves it half
+    // written. Returns the file's offset table.
+    fn write_region(path: &Path, chunk_data: &[Option<Vec<u8>>]) -> io::Result<OffsetTable> {
+        let temp_path = path.with_extension("region.tmp");
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        writer.write_all(REGION_FILE_MAGIC)?;
+        writer.write_all(&REGION_FILE_VERSION.to_le_bytes())?;
+
+        let mut offsets = Vec::with_capacity(CHUNKS_PER_REGION);
+        let mut offset = REGION_HEADER_SIZE as u32;
+        for chunk_bytes in chunk_data {
+            let length = chunk_bytes.as_ref().map_or(0, |bytes| bytes.len() as u32);
+            let entry_offset = if length == 0 { 0 } else { offset };
+            writer.write_all(&entry_offset.to_le_bytes())?;
+            writer.write_all(&length.to_le_bytes())?;
+            offsets.push((entry_offset, length));
+            offset += length;
+        }
+        for chunk_bytes in chunk_data.iter().flatten() {
+            writer.write_all(chunk_bytes)?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(temp_path, path)?;
+        Ok(offsets)
+    }
+}
+
+fn read_offset_table<R: Read>(reader: &mut R) -> io::Result<OffsetTable> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    let mut version = [0; 2];
+    reader.read_exact(&mut version)?;
+    if &magic != REGION_FILE_MAGIC || u16::from_le_bytes(version) != REGION_FILE_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "not a supported region file",
+        ));
+    }
+
+    let mut table = vec![0; CHUNKS_PER_REGION * 8];
+    reader.read_exact(&mut table)?;
+    Ok(table
+        .chunks_exact(8)
+        .map(|entry| {
+            (
+                u32::from_le_bytes([entry[0], entry[1], entry[2], entry[3]]),
+                u32::from_le_bytes([entry[4], entry[5], entry[6], entry[7]]),
+            )
+        })
+        .collect())
+}
+
+fn region_of(chunk_pos: &ChunkPos) -> (i32, i32) {
+    (
+        chunk_pos.x.div_euclid(REGION_SIZE),
+        chunk_pos.y.div_euclid(REGION_SIZE),
+    )
+}
+
+fn index_in_region(chunk_pos: &ChunkPos) -> usize {
+    let x = chunk_pos.x.rem_euclid(REGION_SIZE);
+    let y = chunk_pos.y.rem_euclid(REGION_SIZE);
+    (x + y * REGION_SIZE) as usize
+}
+
+// Paging chunks between memory and region files
+impl World {
+    // Stores the world in region files inside a directory, after which chunks far from the
+    // camera are evicted to disk and loaded back when needed
+    pub fn use_region_storage(&mut self, directory: impl AsRef<Path>) -> io::Result<()> {
+        let storage = RegionStorage::new(directory)?;
+        storage.save_chunks(self.chunks.iter())?;
+        self.region_storage = Some(storage);
+        self.dirty_chunks.clear();
+        Ok(())
+    }
+
+    // Makes sure a chunk is in memory if it is stored on disk, returns if the chunk is loaded
+    pub fn load_chunk(&mut self, chunk_pos: &ChunkPos) -> bool {
+        if self.chunks.contains_key(chunk_pos) {
+            return true;
+        }
+        let Some(storage) = &self.region_storage else {
+            return false;
+        };
+        match storage.load_chunk(chunk_pos) {
+            Ok(Some(chunk)) => {
+                self.chunks.insert(*chunk_pos, chunk);
+                true
+            }
+            Ok(None) => false,
+            Err(err) => {
+                eprintln!("Failed to load chunk {:?}: {}", chunk_pos, err);
+                false
+            }
+        }
+    }
+
+    // Loads or generates chunks around the view and writes chunks far away from it back to disk.
+    // Does nothing for finite worlds that are kept entirely in memory.
+    pub fn update_loaded_chunks(&mut self, view: &ViewRect) {
+        if self.region_storage.is_none() && self.chunk_generator.is_none() {
+            return;
+        }
+        let (top_left, bottom_right) = view.chunk_bounds();
+        let min_chunk_x = top_left.x - CHUNK_LOAD_MARGIN;
+        let min_chunk_y = top_left.y - CHUNK_LOAD_MARGIN;
+        let max_chunk_x = bottom_right.x + CHUNK_LOAD_MARGIN;
+        let max_chunk_y = bottom_right.y + CHUNK_LOAD_MARGIN;
+
+        for chunk_y in min_chunk_y..=max_chunk_y {
+            for chunk_x in min_chunk_x..=max_chunk_x {
+                let chunk_pos = ChunkPos {
+                    x: chunk_x,
+                    y: chunk_y,
+                };
+                if !self.load_chunk(&chunk_pos) {
+                    self.generate_chunk(&chunk_pos);
+                }
+            }
+        }
+
+        // Without region files there is nowhere to evict edited chunks to
+        if self.region_storage.is_none() {
+            return;
+        }
+
+        let evicted: Vec<ChunkPos> = self
+            .chunks
+            .keys()
+            .filter(|chunk_pos| {
+                chunk_pos.x < min_chunk_x - CHUNK_EVICT_DISTANCE
+                    || chunk_pos.x > max_chunk_x + CHUNK_EVICT_DISTANCE
+                    || chunk_pos.y < min_chunk_y - CHUNK_EVICT_DISTANCE
+                    || chunk_pos.y > max_chunk_y + CHUNK_EVICT_DISTANCE
+            })
+            .copied()
+            .collect();
+        if evicted.is_empty() {
+            return;
+        }
+        if let Err(err) = self.save_dirty_chunks(&evicted) {
+            // Keep the chunks in memory rather than losing edits
+            eprintln!("Failed to write chunks to disk: {}", err);
+            return;
+        }
+        for chunk_pos in &evicted {
+            self.chunks.remove(chunk_pos);
+        }
+    }
+
+    // Writes every edited chunk to disk
+    pub fn flush_dirty_chunks(&mut self) -> io::Result<()> {
+        let dirty: Vec<ChunkPos> = self.dirty_chunks.iter().copied().collect();
+        self.save_dirty_chunks(&dirty)
+    }
+
+    // Writes the dirty chunks among chunk_positions to disk, clean chunks are already up to date
+    fn save_dirty_chunks(&mut self, chunk_positions: &[ChunkPos]) -> io::Result<()> {
+        let Some(storage) = &self.region_storage else {
+            return Ok(());
+        };
+        let dirty: Vec<(&ChunkPos, &Chunk)> = chunk_positions
+            .iter()
+            .filter(|chunk_pos| self.dirty_chunks.contains(chunk_pos))
+            .filter_map(|chunk_pos| Some((chunk_pos, self.chunks.get(chunk_pos)?)))
+            .collect();
+        storage.save_chunks(dirty)?;
+        for chunk_pos in chunk_positions {
+            self.dirty_chunks.remove(chunk_pos);
+        }
+        Ok(())
+    }
+}
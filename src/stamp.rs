@@ -0,0 +1,238 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::edit_history::EditHistory;
+use crate::tile_registry::tile_registry;
+use crate::world::*;
+
+// Stamp file layout (all numbers little endian):
+//   magic "WSTP", format version (u16), width (u16), height (u16), then for the terrain,
+//   decoration and object layers the id (u16) of every tile row by row from the top left, with
+//   TileId::RESERVED for empty spots
+const STAMP_FILE_MAGIC: &[u8; 4] = b"WSTP";
+pub const STAMP_FILE_VERSION: u16 = 1;
+pub const STAMP_FILE_EXTENSION: &str = "stamp";
+pub const MAX_STAMP_SIZE: u32 = 1024; // Width and height limit of stamps
+
+// A rectangle of tiles copied out of a world in every layer, to be pasted elsewhere. Terrain is
+// empty where the chunk wasn't loaded when copying, those spots are left alone when pasting.
+// Tile metadata isn't copied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stamp {
+    pub width: u32,
+    pub height: u32,
+    tiles: Vec<[Option<TileId>; 3]>, // Row by row from the top left, indexed by layer id
+}
+
+impl Stamp {
+    // A stamp of the given size with nothing in it. Stamps are 1 to MAX_STAMP_SIZE tiles wide and
+    // high.
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        if !(1..=MAX_STAMP_SIZE).contains(&width) || !(1..=MAX_STAMP_SIZE).contains(&height) {
+            return Err(format!(
+                "stamp size {}x{} is out of range, stamps are 1 to {} tiles on each side",
+                width, height, MAX_STAMP_SIZE
+            ));
+        }
+        Ok(Stamp::blank(width, height))
+    }
+
+    // Copies the tiles of the loaded chunks in the rectangle with two opposite corners, failing
+    // if the rectangle is too large for a stamp
+    pub fn copy(
+        world: &World,
+        corner: &GlobalTilePos,
+        opposite_corner: &GlobalTilePos,
+    ) -> Result<Self, String> {
+        let left = corner.0.min(opposite_corner.0);
+        let top = corner.1.min(opposite_corner.1);
+        // Corners at opposite ends of the world are further apart than a u32 holds, saturating
+        // keeps such sizes out of range instead of wrapping them around
+        let side = |start: i32, end: i32| start.abs_diff(end).saturating_add(1);
+        let mut stamp = Stamp::new(
+            side(corner.0, opposite_corner.0),
+            side(corner.1, opposite_corner.1),
+        )?;
+        for y in 0..stamp.height {
+            for x in 0..stamp.width {
+                let pos = GlobalTilePos(left + x as i32, top + y as i32);
+                for layer in TileLayer::ALL {
+                    stamp.set(x, y, layer, world.get_layer_tile(&pos, layer));
+                }
+            }
+        }
+        Ok(stamp)
+    }
+
+    pub fn get(&self, x: u32, y: u32, layer: TileLayer) -> Option<TileId> {
+        self.tiles[self.index(x, y)][layer.id() as usize]
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, layer: TileLayer, tile: Option<TileId>) {
+        let index = self.index(x, y);
+        self.tiles[index][layer.id() as usize] = tile;
+    }
+
+    // The stamp turned a quarter turn clockwise
+    pub fn rotated_clockwise(&self) -> Self {
+        let mut rotated = Stamp::blank(self.height, self.width);
+        for y in 0..rotated.height {
+            for x in 0..rotated.width {
+                let index = rotated.index(x, y);
+                rotated.tiles[index] = self.tiles[self.index(y, self.height - 1 - x)];
+            }
+        }
+        rotated
+    }
+
+    // The stamp mirrored left to right
+    pub fn flipped_horizontally(&self) -> Self {
+        let mut flipped = self.clone();
+        for row in flipped.tiles.chunks_exact_mut(self.width as usize) {
+            row.reverse();
+        }
+        flipped
+    }
+
+    // The stamp mirrored top to bottom
+    pub fn flipped_vertically(&self) -> Self {
+        let mut flipped = self.clone();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = flipped.index(x, y);
+                flipped.tiles[index] = self.tiles[self.index(x, self.height - 1 - y)];
+            }
+        }
+        flipped
+    }
+
+    // The edits pasting the stamp with its top left corner at a position makes: every overlay
+    // tile, empty or not, and the terrain that was copied
+    pub fn placements(
+        &self,
+        top_left: &GlobalTilePos,
+    ) -> Vec<(GlobalTilePos, TileLayer, Option<TileId>)> {
+        let mut placements = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pos = GlobalTilePos(top_left.0 + x as i32, top_left.1 + y as i32);
+                for layer in TileLayer::ALL {
+                    let tile = self.get(x, y, layer);
+                    if layer != TileLayer::Terrain || tile.is_some() {
+                        placements.push((pos, layer, tile));
+                    }
+                }
+            }
+        }
+        placements
+    }
+
+    // Pastes the stamp with its top left corner at a position, as one undoable action
+    pub fn paste(&self, world: &mut World, history: &mut EditHistory, top_left: &GlobalTilePos) {
+        history.begin_stroke();
+        for (pos, layer, tile) in self.placements(top_left) {
+            history.apply(world, &pos, layer, tile);
+        }
+        history.end_stroke();
+    }
+
+    // Saves the stamp to a file, overwriting it if it already exists
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    // Loads a stamp previously written with Stamp::save
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Stamp> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::read_from(&mut reader)
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if self.width > MAX_STAMP_SIZE || self.height > MAX_STAMP_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "stamp is too large to save",
+            ));
+        }
+        writer.write_all(STAMP_FILE_MAGIC)?;
+        writer.write_all(&STAMP_FILE_VERSION.to_le_bytes())?;
+        writer.write_all(&(self.width as u16).to_le_bytes())?;
+        writer.write_all(&(self.height as u16).to_le_bytes())?;
+        for layer in TileLayer::ALL {
+            for tiles in &self.tiles {
+                let tile = tiles[layer.id() as usize].unwrap_or(TileId::RESERVED);
+                writer.write_all(&tile.0.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<Stamp> {
+        let mut header = [0; 10];
+        reader.read_exact(&mut header)?;
+        if &header[0..4] != STAMP_FILE_MAGIC {
+            return Err(invalid_data("not a stamp file"));
+        }
+        let version = u16::from_le_bytes([header[4], header[5]]);
+        if version != STAMP_FILE_VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported stamp file version {}",
+                version
+            )));
+        }
+        let width = u16::from_le_bytes([header[6], header[7]]) as u32;
+        let height = u16::from_le_bytes([header[8], header[9]]) as u32;
+        let mut stamp = Stamp::new(width, height).map_err(|err| invalid_data(&err))?;
+        let mut bytes = [0; 2];
+        for layer in TileLayer::ALL {
+            for tiles in &mut stamp.tiles {
+                reader.read_exact(&mut bytes)?;
+                let tile = TileId(u16::from_le_bytes(bytes));
+                if tile == TileId::RESERVED {
+                    continue;
+                }
+                if !tile_registry().contains(tile) {
+                    return Err(invalid_data(&format!("unknown tile {}", tile.0)));
+                }
+                tiles[layer.id() as usize] = Some(tile);
+            }
+        }
+        Ok(stamp)
+    }
+
+    // A stamp with nothing in it, the size has to be in range already
+    fn blank(width: u32, height: u32) -> Self {
+        Stamp {
+            width,
+            height,
+            tiles: vec![[None; 3]; width as usize * height as usize],
+        }
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        (x + y * self.width) as usize
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// Stamp files in a directory, sorted by name
+pub fn list_stamps(directory: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == STAMP_FILE_EXTENSION)
+        {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+
+use crate::world::*;
+
+// Keys used by the renderer's own tiles. Mods can use any other key.
+pub const GROWTH_STAGE: &str = "growth_stage";
+pub const OWNER: &str = "owner";
+pub const DAMAGE: &str = "damage";
+pub const TEXT: &str = "text";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataValue {
+    Integer(i64),
+    Text(String),
+}
+
+// State of a single tile beyond its type, like a crop's growth stage or a sign's text
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TileMetadata {
+    pub values: BTreeMap<String, MetadataValue>,
+}
+
+impl TileMetadata {
+    pub fn get(&self, key: &str) -> Option<&MetadataValue> {
+        self.values.get(key)
+    }
+
+    pub fn set(&mut self, key: &str, value: MetadataValue) {
+        self.values.insert(key.to_string(), value);
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<MetadataValue> {
+        self.values.remove(key)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    // Value of a key if it's an integer
+    pub fn integer(&self, key: &str) -> Option<i64> {
+        match self.get(key)? {
+            MetadataValue::Integer(value) => Some(*value),
+            MetadataValue::Text(_) => None,
+        }
+    }
+
+    // Value of a key if it's text
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            MetadataValue::Text(value) => Some(value),
+            MetadataValue::Integer(_) => None,
+        }
+    }
+}
+
+// Metadata of the tiles in a chunk, by layer and local index. Only tiles that have metadata take
+// up space.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChunkMetadata {
+    entries: BTreeMap<(TileLayer, usize), TileMetadata>,
+}
+
+impl ChunkMetadata {
+    pub fn get(&self, layer: TileLayer, local_index: usize) -> Option<&TileMetadata> {
+        self.entries.get(&(layer, local_index))
+    }
+
+    // Metadata of a tile, added empty if the tile had none
+    pub fn get_or_insert(&mut self, layer: TileLayer, local_index: usize) -> &mut TileMetadata {
+        self.entries.entry((layer, local_index)).or_default()
+    }
+
+    pub fn insert(&mut self, layer: TileLayer, local_index: usize, metadata: TileMetadata) {
+        self.entries.insert((layer, local_index), metadata);
+    }
+
+    pub fn remove(&mut self, layer: TileLayer, local_index: usize) -> Option<TileMetadata> {
+        self.entries.remove(&(layer, local_index))
+    }
+
+    // Iterates over the tiles that have metadata, in layer order then by local index. Tiles whose
+    // metadata was emptied are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (TileLayer, usize, &TileMetadata)> {
+        self.entries
+            .iter()
+            .filter(|(_, metadata)| !metadata.is_empty())
+            .map(|((layer, local_index), metadata)| (*layer, *local_index, metadata))
+    }
+}
+
+// Reading and writing tile metadata. Metadata belongs to the tile of one layer at a position and
+// is dropped when World::set_layer_tile replaces or clears that tile.
+impl World {
+    pub fn get_tile_metadata(
+        &self,
+        pos: &GlobalTilePos,
+        layer: TileLayer,
+    ) -> Option<&TileMetadata> {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        self.chunks
+            .get(&chunk_pos)?
+            .metadata
+            .get(layer, local_index)
+    }
+
+    // Gets the metadata of a tile to change it, loading its chunk from disk if needed. Returns None
+    // if there is no tile in the layer at pos.
+    pub fn tile_metadata_mut(
+        &mut self,
+        pos: &GlobalTilePos,
+        layer: TileLayer,
+    ) -> Option<&mut TileMetadata> {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        self.load_chunk(&chunk_pos);
+        let chunk = self.chunks.get_mut(&chunk_pos)?;
+        chunk.layer_tile(layer, local_index)?;
+        chunk.mark_changed();
+        self.dirty_chunks.insert(chunk_pos);
+        Some(chunk.metadata.get_or_insert(layer, local_index))
+    }
+
+    // Replaces the metadata of a tile, returns false if there is no tile in the layer at pos
+    pub fn set_tile_metadata(
+        &mut self,
+        pos: &GlobalTilePos,
+        layer: TileLayer,
+        metadata: TileMetadata,
+    ) -> bool {
+        match self.tile_metadata_mut(pos, layer) {
+            Some(tile_metadata) => {
+                *tile_metadata = metadata;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_tile_metadata(
+        &mut self,
+        pos: &GlobalTilePos,
+        layer: TileLayer,
+    ) -> Option<TileMetadata> {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        self.load_chunk(&chunk_pos);
+        let chunk = self.chunks.get_mut(&chunk_pos)?;
+        let metadata = chunk.metadata.remove(layer, local_index)?;
+        chunk.mark_changed();
+        self.dirty_chunks.insert(chunk_pos);
+        Some(metadata)
+    }
+}
@@ -0,0 +1,279 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::animation::{TileAnimation, MAX_ANIMATION_FRAMES};
+use crate::assets::atlas_lookup::{TILE_ATLAS_COLUMNS, TILE_ATLAS_ROWS};
+use crate::world::TileLayer;
+
+// Definitions of the tiles that ship with the renderer
+pub const BUILTIN_TILES_TOML: &str = include_str!("assets/tiles/tiles.toml");
+
+// Most tiles a registry can hold. Every tile gets a row of the autotile atlas, this keeps the
+// atlas texture within the 8192 pixels GPUs commonly allow.
+pub const MAX_TILES: usize = 800;
+
+// Compact id of a tile type, stored in chunks and world files. What the tile looks like and how
+// it behaves is looked up in the tile registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileId(pub u16);
+
+// Builtin tiles, see assets/tiles/tiles.toml
+impl TileId {
+    pub const GRASS: TileId = TileId(0);
+    pub const STONE: TileId = TileId(1);
+    pub const SAND: TileId = TileId(2);
+    pub const WATER: TileId = TileId(3);
+    pub const SHALLOW_WATER: TileId = TileId(4);
+    pub const DEEP_WATER: TileId = TileId(5);
+    pub const DARK_STONE: TileId = TileId(6);
+    pub const SNOW: TileId = TileId(7);
+    pub const FLOWERS: TileId = TileId(8);
+    pub const ROCK: TileId = TileId(9);
+    pub const TREE: TileId = TileId(10);
+
+    // Never given to a tile, world files use it for empty spots in overlay layers
+    pub const RESERVED: TileId = TileId(u16::MAX);
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TileDefinition {
+    pub id: TileId,
+    pub name: String,
+    pub atlas: [u32; 2], // Column and row of the tile's sprite in the tile atlas
+    pub color: [u8; 3],  // Used on exported maps and when zoomed out
+    #[serde(default)]
+    pub layer: TileLayer, // Layer the tile is placed in
+    #[serde(default)]
+    pub walkable: bool,
+    #[serde(default)]
+    pub liquid: bool,
+    #[serde(default)]
+    pub solid: bool,
+    #[serde(default)]
+    pub animation: Option<TileAnimation>,
+}
+
+// Layout of a tile definitions file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TileDefinitionsFile {
+    tile: Vec<TileDefinition>,
+}
+
+// Every tile type a world can contain, by id and by name
+#[derive(Debug, Clone, Default)]
+pub struct TileRegistry {
+    definitions: Vec<Option<TileDefinition>>, // Indexed by id
+    ids_by_name: HashMap<String, TileId>,
+    atlas_rows: Vec<Option<u16>>, // Row of every tile in the autotile atlas, indexed by id
+}
+
+impl TileRegistry {
+    // Registry of just the builtin tiles
+    pub fn builtin() -> Self {
+        let mut registry = TileRegistry::default();
+        registry
+            .add_from_toml_str(BUILTIN_TILES_TOML)
+            .expect("builtin tile definitions are valid");
+        registry
+    }
+
+    // Adds the tiles of a definitions file, for example a mod's
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        self.add_from_toml_str(&text)
+    }
+
+    // Adds every tile in a definitions file's text, or none of them if one is invalid
+    pub fn add_from_toml_str(&mut self, text: &str) -> Result<(), String> {
+        let file: TileDefinitionsFile = toml::from_str(text).map_err(|err| err.to_string())?;
+        let mut registry = self.clone();
+        for definition in file.tile {
+            registry.add(definition)?;
+        }
+        *self = registry;
+        Ok(())
+    }
+
+    // Adds a tile after checking it doesn't clash with the tiles already registered
+    pub fn add(&mut self, definition: TileDefinition) -> Result<(), String> {
+        if definition.name.is_empty() {
+            return Err(format!("tile {} has no name", definition.id.0));
+        }
+        if definition.id == TileId::RESERVED {
+            return Err(format!(
+                "tile \"{}\" uses the reserved id {}",
+                definition.name,
+                TileId::RESERVED.0
+            ));
+        }
+        if let Some(existing) = self.get(definition.id) {
+            return Err(format!(
+                "tiles \"{}\" and \"{}\" both use id {}",
+                existing.name, definition.name, definition.id.0
+            ));
+        }
+        if self.ids_by_name.contains_key(&definition.name) {
+            return Err(format!(
+                "more than one tile is named \"{}\"",
+                definition.name
+            ));
+        }
+        if self.len() >= MAX_TILES {
+            return Err(format!(
+                "tile \"{}\" doesn't fit, at most {} tiles can be registered",
+                definition.name, MAX_TILES
+            ));
+        }
+        let [column, row] = definition.atlas;
+        if column >= TILE_ATLAS_COLUMNS || row >= TILE_ATLAS_ROWS {
+            return Err(format!(
+                "tile \"{}\" uses atlas slot [{}, {}], the atlas is {} by {}",
+                definition.name, column, row, TILE_ATLAS_COLUMNS, TILE_ATLAS_ROWS
+            ));
+        }
+        if let Some(animation) = &definition.animation {
+            let frame_count = animation.frame_durations.len();
+            if frame_count == 0 || frame_count > MAX_ANIMATION_FRAMES {
+                return Err(format!(
+                    "tile \"{}\" has {} animation frames, expected 1 to {}",
+                    definition.name, frame_count, MAX_ANIMATION_FRAMES
+                ));
+            }
+            if animation
+                .frame_durations
+                .iter()
+                .any(|duration| !(*duration > 0.0 && duration.is_finite()))
+            {
+                return Err(format!(
+                    "tile \"{}\" has an animation frame that doesn't last a positive time",
+                    definition.name
+                ));
+            }
+        }
+
+        let index = definition.id.0 as usize;
+        if index >= self.definitions.len() {
+            self.definitions.resize(index + 1, None);
+        }
+        self.ids_by_name
+            .insert(definition.name.clone(), definition.id);
+        self.definitions[index] = Some(definition);
+
+        // Rows follow the order of the ids, so tiles with any id share a compact atlas
+        let mut atlas_rows = vec![None; self.definitions.len()];
+        for (row, definition) in self.iter().enumerate() {
+            atlas_rows[definition.id.0 as usize] = Some(row as u16);
+        }
+        self.atlas_rows = atlas_rows;
+        Ok(())
+    }
+
+    pub fn get(&self, id: TileId) -> Option<&TileDefinition> {
+        self.definitions.get(id.0 as usize)?.as_ref()
+    }
+
+    pub fn contains(&self, id: TileId) -> bool {
+        self.get(id).is_some()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<TileId> {
+        self.ids_by_name.get(name).copied()
+    }
+
+    // Every registered tile, by id
+    pub fn iter(&self) -> impl Iterator<Item = &TileDefinition> {
+        self.definitions.iter().flatten()
+    }
+
+    // Number of registered tiles
+    pub fn len(&self) -> usize {
+        self.ids_by_name.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids_by_name.is_empty()
+    }
+
+    // Row of a tile in the autotile atlas, None for tiles that aren't registered
+    pub fn atlas_row(&self, id: TileId) -> Option<u16> {
+        *self.atlas_rows.get(id.0 as usize)?
+    }
+}
+
+// The registry used by the whole program. Starts out with the builtin tiles unless another
+// registry is installed before any tile is looked up.
+static TILE_REGISTRY: OnceLock<TileRegistry> = OnceLock::new();
+
+pub fn tile_registry() -> &'static TileRegistry {
+    TILE_REGISTRY.get_or_init(TileRegistry::builtin)
+}
+
+// Makes the registry the one used by the whole program. Fails once tiles have been looked up,
+// as chunks may already hold ids of the old registry.
+pub fn install_tile_registry(registry: TileRegistry) -> Result<(), String> {
+    TILE_REGISTRY
+        .set(registry)
+        .map_err(|_| "the tile registry is already in use".to_string())
+}
+
+// Lookups in the installed tile registry
+impl TileId {
+    pub fn definition(&self) -> Option<&'static TileDefinition> {
+        tile_registry().get(*self)
+    }
+
+    // Name of the tile, or its id for tiles that aren't registered
+    pub fn name(&self) -> String {
+        match self.definition() {
+            Some(definition) => definition.name.clone(),
+            None => format!("#{}", self.0),
+        }
+    }
+
+    // Color of the tile on maps, transparent for tiles that aren't registered
+    pub fn map_color(&self) -> [u8; 4] {
+        match self.definition() {
+            Some(definition) => {
+                let [r, g, b] = definition.color;
+                [r, g, b, 255]
+            }
+            None => [0, 0, 0, 0],
+        }
+    }
+}
+
+// Tiles are written as their id in definitions files, and as their name in presets
+impl<'de> Deserialize<'de> for TileId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TileIdVisitor;
+
+        impl Visitor<'_> for TileIdVisitor {
+            type Value = TileId;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a tile name or id")
+            }
+
+            fn visit_i64<E: de::Error>(self, id: i64) -> Result<TileId, E> {
+                u16::try_from(id)
+                    .map(TileId)
+                    .map_err(|_| E::custom(format!("tile id {} is out of range", id)))
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<TileId, E> {
+                tile_registry()
+                    .lookup(name)
+                    .ok_or_else(|| E::custom(format!("unknown tile \"{}\"", name)))
+            }
+        }
+
+        deserializer.deserialize_any(TileIdVisitor)
+    }
+}
@@ -0,0 +1,43 @@
+use macroquad::{
+    prelude::{ImageFormat, Rect},
+    texture::{FilterMode, Texture2D},
+};
+
+use crate::assets::{AtlasRect, TILE_ATLAS_PNG};
+use crate::autotile::load_autotile_atlas;
+
+// Spritesheet for the tiles
+pub struct TileAtlas(pub Texture2D);
+
+pub struct AssetHandle {
+    pub tile_atlas: TileAtlas,
+    pub autotile_atlas: TileAtlas, // Tile sprites with edge transitions, see autotile.rs
+}
+
+impl AssetHandle {
+    pub fn new() -> Self {
+        let embedded_tile_atlas = Self::load_embedded_asset(TILE_ATLAS_PNG);
+        let autotile_atlas = load_autotile_atlas();
+        let autotile_atlas = Texture2D::from_rgba8(
+            autotile_atlas.width() as u16,
+            autotile_atlas.height() as u16,
+            autotile_atlas.as_raw(),
+        );
+        autotile_atlas.set_filter(FilterMode::Nearest);
+        AssetHandle {
+            tile_atlas: TileAtlas(embedded_tile_atlas),
+            autotile_atlas: TileAtlas(autotile_atlas),
+        }
+    }
+    pub fn load_embedded_asset(file_bytes: &[u8]) -> Texture2D {
+        let texture = Texture2D::from_file_with_format(file_bytes, Some(ImageFormat::Png));
+        texture.set_filter(FilterMode::Nearest);
+        return texture;
+    }
+}
+
+impl From<AtlasRect> for Rect {
+    fn from(rect: AtlasRect) -> Self {
+        Rect::new(rect.x, rect.y, rect.w, rect.h)
+    }
+}
@@ -0,0 +1,140 @@
+use macroquad::prelude::*;
+
+use crate::assets::atlas_lookup::TILE_SIZE;
+use crate::viewer::input_map::{Action, ActionState};
+use crate::world::*;
+
+pub fn make_camera() -> Camera2D {
+    Camera2D {
+        zoom: vec2(
+            1.0 / screen_width() * TILE_SIZE,
+            1.0 / screen_height() * TILE_SIZE,
+        ),
+        target: vec2(0.0, 0.0),
+        render_target: None,
+        offset: vec2(0.0, 0.0),
+        rotation: 0.0,
+        viewport: None,
+    }
+}
+
+pub const MIN_ZOOM: f32 = 1.0; // Widest view
+pub const MAX_ZOOM: f32 = 16.0; // Closest view
+const ZOOM_STEP: f32 = 1.25; // Zoom factor of one wheel notch or key press
+const ZOOM_SMOOTHING: f32 = 12.0; // How fast the zoom eases to its target, higher is faster
+
+// Camera movement that spans frames: the zoom easing towards the zoom asked for, and the world
+// point grabbed by a mouse drag. Zooming keeps the world point under the mouse in place.
+pub struct CameraControls {
+    pub zoom: f32,        // From MIN_ZOOM to MAX_ZOOM, scales how many pixels a tile covers
+    pub target_zoom: f32, // Zoom being eased towards
+    grab: Option<Vec2>,   // World point held under the mouse while PanDrag is held
+}
+
+impl CameraControls {
+    pub fn new(zoom: f32) -> Self {
+        let zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        CameraControls {
+            zoom,
+            target_zoom: zoom,
+            grab: None,
+        }
+    }
+
+    // Pans and zooms the camera for one frame and sets its zoom for the screen size. Doesn't touch
+    // macroquad, so recorded actions can be replayed without a window.
+    pub fn handle_input(
+        &mut self,
+        camera: &mut Camera2D,
+        actions: &ActionState,
+        mouse_position: Vec2,
+        screen_size: Vec2,
+        frame_time: f32,
+    ) {
+        camera.zoom = self.zoom / screen_size;
+        let camera_speed = 1.0 / { camera.zoom.x + camera.zoom.y }; // Pan speed increases with less zoom
+        if actions.is_down(Action::PanUp) {
+            camera.target.y += camera_speed * frame_time;
+        }
+        if actions.is_down(Action::PanDown) {
+            camera.target.y -= camera_speed * frame_time;
+        }
+        if actions.is_down(Action::PanLeft) {
+            camera.target.x -= camera_speed * frame_time;
+        }
+        if actions.is_down(Action::PanRight) {
+            camera.target.x += camera_speed * frame_time;
+        }
+
+        // Platforms report a wheel notch as anything from 1 to 120, so a frame zooms at most one
+        // step. Smaller amounts, like from touchpads, zoom part of a step.
+        let mouse_world_pos = screen_to_world(camera, mouse_position, screen_size);
+        let steps = zoom_steps(actions, Action::ZoomIn) - zoom_steps(actions, Action::ZoomOut);
+        self.target_zoom = (self.target_zoom * ZOOM_STEP.powf(steps)).clamp(MIN_ZOOM, MAX_ZOOM);
+
+        // Eases the zoom exponentially, in steps that add up the same at any frame rate
+        let previous_zoom = self.zoom;
+        let ease = 1.0 - (-ZOOM_SMOOTHING * frame_time).exp();
+        self.zoom = (self.zoom.ln() + (self.target_zoom.ln() - self.zoom.ln()) * ease).exp();
+        if (self.zoom / self.target_zoom - 1.0).abs() < 0.001 {
+            self.zoom = self.target_zoom;
+        }
+        camera.zoom = self.zoom / screen_size;
+
+        if actions.is_down(Action::PanDrag) {
+            // The grabbed point follows the mouse, and stays under it while zooming
+            let grab = *self.grab.get_or_insert(mouse_world_pos);
+            place_under(camera, grab, mouse_position, screen_size);
+        } else {
+            self.grab = None;
+            if self.zoom != previous_zoom {
+                place_under(camera, mouse_world_pos, mouse_position, screen_size);
+            }
+        }
+    }
+}
+
+// Steps to zoom by for an action this frame, see CameraControls::handle_input
+fn zoom_steps(actions: &ActionState, action: Action) -> f32 {
+    if actions.is_pressed(action) {
+        actions.value(action).min(1.0)
+    } else {
+        0.0
+    }
+}
+
+// Same as Camera2D::screen_to_world for unrotated cameras, for a screen of the given size
+pub fn screen_to_world(camera: &Camera2D, point: Vec2, screen_size: Vec2) -> Vec2 {
+    let clip_pos = vec2(
+        point.x / screen_size.x * 2.0 - 1.0,
+        1.0 - point.y / screen_size.y * 2.0,
+    );
+    camera.target + (clip_pos - camera.offset) / camera.zoom
+}
+
+// Moves the camera so a world point is at a point on the screen
+fn place_under(camera: &mut Camera2D, world_pos: Vec2, point: Vec2, screen_size: Vec2) {
+    camera.target += world_pos - screen_to_world(camera, point, screen_size);
+}
+
+// Area of the world visible to the camera, in tiles
+pub fn camera_view_rect(camera: &Camera2D) -> ViewRect {
+    let top_left_grid_pos = camera.screen_to_world(Vec2 { x: 0.0, y: 0.0 }) / TILE_SIZE;
+    let bottom_right_grid_pos = camera.screen_to_world(Vec2 {
+        x: screen_width(),
+        y: screen_height(),
+    }) / TILE_SIZE;
+    // World space y points up, tile y points down
+    ViewRect {
+        left: top_left_grid_pos.x,
+        top: -top_left_grid_pos.y,
+        right: bottom_right_grid_pos.x,
+        bottom: -bottom_right_grid_pos.y,
+    }
+}
+
+// Tile under the mouse cursor
+pub fn mouse_tile_pos(camera: &Camera2D) -> GlobalTilePos {
+    let grid_pos = camera.screen_to_world(mouse_position().into()) / TILE_SIZE;
+    GlobalTilePos(grid_pos.x.floor() as i32, (-grid_pos.y).floor() as i32)
+}
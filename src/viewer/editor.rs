@@ -0,0 +1,392 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use macroquad::prelude::*;
+
+use crate::assets::atlas_lookup::TILE_SIZE;
+use crate::edit_history::EditHistory;
+use crate::editor_tools::*;
+use crate::stamp::*;
+use crate::tile_registry::tile_registry;
+use crate::viewer::camera::mouse_tile_pos;
+use crate::viewer::input::STAMP_DIRECTORY;
+use crate::viewer::input_map::{Action, ActionState};
+use crate::world::*;
+
+const MAX_BRUSH_RADIUS: u32 = 16;
+const PREVIEW_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.35);
+const SELECTION_COLOR: Color = Color::new(0.3, 0.6, 1.0, 0.5);
+const PASTE_PREVIEW_ALPHA: f32 = 0.6;
+
+// The selected editing tool and tile. The Paint action uses the tool with the selected tile, Erase
+// clears the selected tile's layer instead (terrain can't be cleared). Every use of a tool is one
+// undoable action. Line, rectangle, ellipse and select work by dragging, selecting a tool again
+// switches between its variants. Stamps are pasted centered on the mouse. See bindings.toml for
+// every action and its default keys.
+pub struct TileEditor {
+    pub tool: EditorTool,
+    pub tile: TileId,
+    pub brush_radius: u32,
+    pub selection: Option<(GlobalTilePos, GlobalTilePos)>, // Opposite corners of the selected area
+    pub clipboard: Option<Stamp>,
+    pub message: Option<String>, // Shown on the editor panel until the next click on the world
+    library_index: usize,        // Stamp of the library LoadStamp loads next
+    last_brush_pos: Option<GlobalTilePos>, // Where the brush was last frame while painting
+    drag: Option<(GlobalTilePos, Option<TileId>)>, // Start and tile of the shape being dragged out
+}
+
+impl TileEditor {
+    pub fn new() -> Self {
+        TileEditor {
+            tool: EditorTool::Brush(BrushShape::Circle),
+            tile: TileId::GRASS,
+            brush_radius: 0,
+            selection: None,
+            clipboard: None,
+            message: None,
+            library_index: 0,
+            last_brush_pos: None,
+            drag: None,
+        }
+    }
+
+    // Layer the selected tile is placed in
+    pub fn layer(&self) -> TileLayer {
+        self.tile
+            .definition()
+            .map_or(TileLayer::Terrain, |definition| definition.layer)
+    }
+
+    // Edits the world with the tool at the tile under the mouse. Doesn't touch macroquad, so
+    // recorded actions can be replayed without a window.
+    pub fn handle_input(
+        &mut self,
+        actions: &ActionState,
+        mouse_pos: GlobalTilePos,
+        world: &mut World,
+        history: &mut EditHistory,
+        pointer_over_ui: bool,
+    ) {
+        let previous_tool = self.tool;
+        self.handle_tool_actions(actions);
+        self.handle_clipboard_actions(actions, world);
+        if self.tool != previous_tool {
+            // Switching tools mid stroke or drag ends it
+            history.end_stroke();
+            self.last_brush_pos = None;
+            self.drag = None;
+        }
+
+        if actions.is_pressed(Action::Undo) {
+            history.undo(world);
+        }
+        if actions.is_pressed(Action::Redo) {
+            history.redo(world);
+        }
+
+        // Clicks on the editor panel are left to it, but a stroke or drag started on the world
+        // carries on while the mouse is over the panel, until the button is released
+        let in_progress = self.last_brush_pos.is_some() || self.drag.is_some();
+        let down = |action| (in_progress || !pointer_over_ui) && actions.is_down(action);
+        let pressed = |action| !pointer_over_ui && actions.is_pressed(action);
+        if pressed(Action::Paint) || pressed(Action::Erase) {
+            self.message = None;
+        }
+        let held_tile = action_tile(self.tile, down);
+        let layer = self.layer();
+        match self.tool {
+            EditorTool::Brush(shape) => match held_tile {
+                Some(tile) => {
+                    history.begin_stroke();
+                    let start = self.last_brush_pos.unwrap_or(mouse_pos);
+                    for pos in brush_stroke(shape, &start, &mouse_pos, self.brush_radius) {
+                        history.apply(world, &pos, layer, tile);
+                    }
+                    self.last_brush_pos = Some(mouse_pos);
+                }
+                None => {
+                    history.end_stroke();
+                    self.last_brush_pos = None;
+                }
+            },
+            EditorTool::Line | EditorTool::Rectangle { .. } | EditorTool::Ellipse { .. } => {
+                match (held_tile, self.drag) {
+                    (Some(tile), None) => self.drag = Some((mouse_pos, tile)),
+                    (None, Some((start, tile))) => {
+                        // The shape is placed once the button is released
+                        self.drag = None;
+                        apply_tiles(
+                            world,
+                            history,
+                            &self.shape_tiles(&start, &mouse_pos),
+                            layer,
+                            tile,
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            EditorTool::FloodFill => {
+                if let Some(tile) = action_tile(self.tile, pressed) {
+                    match flood_fill(world, &mouse_pos, layer, MAX_FLOOD_FILL_TILES) {
+                        Some(tiles) => apply_tiles(world, history, &tiles, layer, tile),
+                        None => self.message = Some("The area is too large to fill".to_string()),
+                    }
+                }
+            }
+            EditorTool::Eyedropper => {
+                if pressed(Action::Paint) {
+                    if let Some(tile) = pick_tile(world, &mouse_pos) {
+                        self.tile = tile;
+                    }
+                }
+            }
+            EditorTool::Select => match (held_tile, self.drag) {
+                (Some(_), None) => self.drag = Some((mouse_pos, None)),
+                (None, Some((start, _))) => {
+                    self.drag = None;
+                    self.selection = Some((start, mouse_pos));
+                }
+                _ => {}
+            },
+            EditorTool::Paste => {
+                if let Some(stamp) = &self.clipboard {
+                    if pressed(Action::Paint) {
+                        stamp.paste(world, history, &paste_origin(stamp, &mouse_pos));
+                    }
+                }
+            }
+        }
+    }
+
+    // Outlines the tiles the tool would change, in world space
+    pub fn draw_preview(&self, camera: &Camera2D) {
+        // The selection stays outlined while other tools are used
+        if let Some((corner, opposite_corner)) = self.selection {
+            for pos in rectangle(&corner, &opposite_corner, false) {
+                draw_tile_highlight(&pos, SELECTION_COLOR);
+            }
+        }
+
+        let mouse_pos = mouse_tile_pos(camera);
+        if let (EditorTool::Paste, Some(stamp)) = (self.tool, &self.clipboard) {
+            draw_stamp_preview(stamp, &paste_origin(stamp, &mouse_pos));
+            return;
+        }
+        let tiles = match (self.tool, self.drag) {
+            (EditorTool::Brush(shape), _) => brush(shape, &mouse_pos, self.brush_radius),
+            (_, Some((start, _))) => self.shape_tiles(&start, &mouse_pos),
+            _ => vec![mouse_pos],
+        };
+        for pos in tiles {
+            draw_tile_highlight(&pos, PREVIEW_COLOR);
+        }
+    }
+
+    fn handle_tool_actions(&mut self, actions: &ActionState) {
+        // Selecting the current tool again switches its variant
+        self.tool = match self.tool {
+            EditorTool::Brush(BrushShape::Circle) if actions.is_pressed(Action::BrushTool) => {
+                EditorTool::Brush(BrushShape::Square)
+            }
+            _ if actions.is_pressed(Action::BrushTool) => EditorTool::Brush(BrushShape::Circle),
+            _ if actions.is_pressed(Action::LineTool) => EditorTool::Line,
+            EditorTool::Rectangle { filled } if actions.is_pressed(Action::RectangleTool) => {
+                EditorTool::Rectangle { filled: !filled }
+            }
+            _ if actions.is_pressed(Action::RectangleTool) => {
+                EditorTool::Rectangle { filled: false }
+            }
+            EditorTool::Ellipse { filled } if actions.is_pressed(Action::EllipseTool) => {
+                EditorTool::Ellipse { filled: !filled }
+            }
+            _ if actions.is_pressed(Action::EllipseTool) => EditorTool::Ellipse { filled: false },
+            _ if actions.is_pressed(Action::FloodFillTool) => EditorTool::FloodFill,
+            _ if actions.is_pressed(Action::EyedropperTool) => EditorTool::Eyedropper,
+            _ if actions.is_pressed(Action::SelectTool) => EditorTool::Select,
+            tool => tool,
+        };
+
+        if let (EditorTool::Paste, Some(stamp)) = (self.tool, &mut self.clipboard) {
+            if actions.is_pressed(Action::RotateStamp) {
+                *stamp = stamp.rotated_clockwise();
+            }
+            if actions.is_pressed(Action::FlipStampHorizontally) {
+                *stamp = stamp.flipped_horizontally();
+            }
+            if actions.is_pressed(Action::FlipStampVertically) {
+                *stamp = stamp.flipped_vertically();
+            }
+        }
+
+        if actions.is_pressed(Action::BrushSmaller) {
+            self.brush_radius = self.brush_radius.saturating_sub(1);
+        }
+        if actions.is_pressed(Action::BrushLarger) {
+            self.brush_radius = (self.brush_radius + 1).min(MAX_BRUSH_RADIUS);
+        }
+
+        if actions.is_pressed(Action::PreviousTile) {
+            self.tile = next_registered_tile(self.tile, true);
+        }
+        if actions.is_pressed(Action::NextTile) {
+            self.tile = next_registered_tile(self.tile, false);
+        }
+        for action in actions.pressed_actions() {
+            if let Action::PaintTile(tile) = action {
+                self.tile = tile;
+            }
+        }
+    }
+
+    fn handle_clipboard_actions(&mut self, actions: &ActionState, world: &World) {
+        if actions.is_pressed(Action::CopySelection) {
+            if let Some((corner, opposite_corner)) = self.selection {
+                match Stamp::copy(world, &corner, &opposite_corner) {
+                    Ok(stamp) => self.clipboard = Some(stamp),
+                    Err(err) => self.message = Some(format!("Can't copy the selection: {}", err)),
+                }
+            }
+        }
+        if actions.is_pressed(Action::PasteStamp) && self.clipboard.is_some() {
+            self.tool = EditorTool::Paste;
+        }
+
+        if actions.is_pressed(Action::SaveStamp) {
+            if let Some(stamp) = &self.clipboard {
+                match save_to_library(stamp, STAMP_DIRECTORY) {
+                    Ok(path) => println!("Saved stamp to {}", path.display()),
+                    Err(err) => println!("Failed to save stamp to {}: {}", STAMP_DIRECTORY, err),
+                }
+            }
+        }
+        if actions.is_pressed(Action::LoadStamp) {
+            let stamps = list_stamps(STAMP_DIRECTORY).unwrap_or_default();
+            if stamps.is_empty() {
+                println!("There are no stamps in {}", STAMP_DIRECTORY);
+                return;
+            }
+            let path = &stamps[self.library_index % stamps.len()];
+            self.library_index += 1;
+            match Stamp::load(path) {
+                Ok(stamp) => {
+                    println!("Loaded stamp {}", path.display());
+                    self.clipboard = Some(stamp);
+                    self.tool = EditorTool::Paste;
+                }
+                Err(err) => println!("Failed to load stamp {}: {}", path.display(), err),
+            }
+        }
+    }
+
+    fn shape_tiles(&self, start: &GlobalTilePos, end: &GlobalTilePos) -> Vec<GlobalTilePos> {
+        match self.tool {
+            EditorTool::Rectangle { filled } => rectangle(start, end, filled),
+            EditorTool::Select => rectangle(start, end, false),
+            EditorTool::Ellipse { filled } => ellipse(start, end, filled),
+            _ => line(start, end),
+        }
+    }
+}
+
+impl Default for TileEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Places or clears the tiles as one undoable action
+fn apply_tiles(
+    world: &mut World,
+    history: &mut EditHistory,
+    tiles: &[GlobalTilePos],
+    layer: TileLayer,
+    tile: Option<TileId>,
+) {
+    history.begin_stroke();
+    for pos in tiles {
+        history.apply(world, pos, layer, tile);
+    }
+    history.end_stroke();
+}
+
+// Top left corner of a stamp pasted centered on a tile
+fn paste_origin(stamp: &Stamp, center: &GlobalTilePos) -> GlobalTilePos {
+    GlobalTilePos(
+        center.0 - stamp.width as i32 / 2,
+        center.1 - stamp.height as i32 / 2,
+    )
+}
+
+// Saves a stamp to the first free numbered file in the directory, creating it if needed
+fn save_to_library(stamp: &Stamp, directory: impl AsRef<Path>) -> io::Result<PathBuf> {
+    fs::create_dir_all(&directory)?;
+    let path = (0..)
+        .map(|number| {
+            directory
+                .as_ref()
+                .join(format!("stamp_{}.{}", number, STAMP_FILE_EXTENSION))
+        })
+        .find(|path| !path.exists())
+        .expect("there is a free stamp number");
+    stamp.save(&path)?;
+    Ok(path)
+}
+
+fn draw_tile_highlight(pos: &GlobalTilePos, color: Color) {
+    draw_rectangle(
+        pos.0 as f32 * TILE_SIZE,
+        -(pos.1 + 1) as f32 * TILE_SIZE,
+        TILE_SIZE,
+        TILE_SIZE,
+        color,
+    );
+}
+
+// Shows where a stamp would be pasted, in the map colors of its uppermost tiles
+fn draw_stamp_preview(stamp: &Stamp, top_left: &GlobalTilePos) {
+    for y in 0..stamp.height {
+        for x in 0..stamp.width {
+            let top_tile = TileLayer::ALL
+                .iter()
+                .rev()
+                .find_map(|layer| stamp.get(x, y, *layer));
+            if let Some(tile) = top_tile {
+                let [r, g, b, _] = tile.map_color();
+                let pos = GlobalTilePos(top_left.0 + x as i32, top_left.1 + y as i32);
+                draw_tile_highlight(
+                    &pos,
+                    Color::from_rgba(r, g, b, (PASTE_PREVIEW_ALPHA * 255.0) as u8),
+                );
+            }
+        }
+    }
+}
+
+// What Paint (the tile) or Erase (None, for clearing) places, if it's in the state action_state
+// checks for
+fn action_tile(tile: TileId, action_state: impl Fn(Action) -> bool) -> Option<Option<TileId>> {
+    if action_state(Action::Paint) {
+        Some(Some(tile))
+    } else if action_state(Action::Erase) {
+        Some(None)
+    } else {
+        None
+    }
+}
+
+// The registered tile before or after a tile by id, wrapping around
+fn next_registered_tile(tile: TileId, previous: bool) -> TileId {
+    let tiles: Vec<TileId> = tile_registry()
+        .iter()
+        .map(|definition| definition.id)
+        .collect();
+    let index = tiles.iter().position(|id| *id == tile).unwrap_or(0);
+    let next_index = if previous {
+        (index + tiles.len() - 1) % tiles.len()
+    } else {
+        (index + 1) % tiles.len()
+    };
+    tiles[next_index]
+}
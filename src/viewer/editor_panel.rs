@@ -0,0 +1,175 @@
+use macroquad::prelude::*;
+
+use crate::assets::atlas_lookup;
+use crate::editor_tools::EditorTool;
+use crate::tile_registry::{tile_registry, TileDefinition};
+use crate::viewer::assets::AssetHandle;
+use crate::viewer::camera::mouse_tile_pos;
+use crate::viewer::editor::TileEditor;
+use crate::viewer::input_map::{Action, ActionState, InputMap};
+use crate::world::*;
+
+const PANEL_MARGIN: f32 = 10.0; // Space between the panel and the edge of the screen
+const PANEL_PADDING: f32 = 10.0;
+const PANEL_COLOR: Color = Color::new(0.1, 0.1, 0.12, 0.85);
+const FONT_SIZE: f32 = 18.0;
+const LINE_HEIGHT: f32 = 22.0;
+const INFO_LINES: usize = 7;
+const PALETTE_COLUMNS: usize = 6;
+const PALETTE_ENTRY_SIZE: f32 = 40.0;
+const PALETTE_ICON_SIZE: f32 = 32.0; // Tile sprites scaled up 4 times
+const PANEL_WIDTH: f32 = PALETTE_COLUMNS as f32 * PALETTE_ENTRY_SIZE + PANEL_PADDING * 2.0;
+
+// Editor overlay along the right edge of the screen: the selected tool and tile, what is under
+// the mouse, the editor's last message and a palette of every registered tile, where clicking a
+// tile selects it for painting. Drawn immediate mode every frame in screen space, the TogglePanel
+// action shows and hides it.
+pub struct EditorPanel {
+    pub visible: bool,
+}
+
+impl EditorPanel {
+    pub fn new() -> Self {
+        EditorPanel { visible: true }
+    }
+
+    pub fn handle_input(&mut self, actions: &ActionState) {
+        if actions.is_pressed(Action::TogglePanel) {
+            self.visible = !self.visible;
+        }
+    }
+
+    // Area of the panel on screen
+    pub fn rect(&self) -> Rect {
+        let palette_rows = tile_registry().iter().count().div_ceil(PALETTE_COLUMNS);
+        let height = PANEL_PADDING * 2.0
+            + INFO_LINES as f32 * LINE_HEIGHT
+            + palette_rows as f32 * PALETTE_ENTRY_SIZE;
+        Rect::new(
+            screen_width() - PANEL_WIDTH - PANEL_MARGIN,
+            PANEL_MARGIN,
+            PANEL_WIDTH,
+            height,
+        )
+    }
+
+    // Whether the mouse is on the panel, so clicks there don't edit the world below
+    pub fn contains_mouse(&self) -> bool {
+        self.visible && self.rect().contains(mouse_position().into())
+    }
+
+    // Draws the panel and selects the palette tile clicked on with Paint. Must be called in screen
+    // space.
+    pub fn draw(
+        &self,
+        editor: &mut TileEditor,
+        world: &World,
+        camera: &Camera2D,
+        asset_handle: &AssetHandle,
+        actions: &ActionState,
+        input_map: &InputMap,
+    ) {
+        if !self.visible {
+            return;
+        }
+        let rect = self.rect();
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, PANEL_COLOR);
+
+        let tool = match editor.tool {
+            EditorTool::Brush(_) => {
+                format!("{}, radius {}", editor.tool.name(), editor.brush_radius)
+            }
+            tool => tool.name().to_string(),
+        };
+        let mouse_pos = mouse_tile_pos(camera);
+        let chunk_pos = mouse_pos.chunk_pos();
+        let hovered_tiles: Vec<String> = TileLayer::ALL
+            .iter()
+            .filter_map(|layer| world.get_layer_tile(&mouse_pos, *layer))
+            .map(|tile| tile.name())
+            .collect();
+        let hovered_tiles = if hovered_tiles.is_empty() {
+            "not loaded".to_string()
+        } else {
+            hovered_tiles.join(", ")
+        };
+        let lines: [String; INFO_LINES] = [
+            tool,
+            format!("Paint: {} ({:?})", editor.tile.name(), editor.layer()),
+            format!("Tile: {}, {}", mouse_pos.0, mouse_pos.1),
+            format!("Chunk: {}, {}", chunk_pos.x, chunk_pos.y),
+            format!("Hovered: {}", hovered_tiles),
+            match input_map.bindings(Action::TogglePanel).first() {
+                Some(binding) => format!("{} hides this panel", binding),
+                None => String::new(),
+            },
+            editor.message.clone().unwrap_or_default(),
+        ];
+        for (index, line) in lines.iter().enumerate() {
+            // Text is placed by its baseline
+            draw_text(
+                line,
+                rect.x + PANEL_PADDING,
+                rect.y + PANEL_PADDING + (index as f32 + 0.75) * LINE_HEIGHT,
+                FONT_SIZE,
+                WHITE,
+            );
+        }
+
+        let palette_top = rect.y + PANEL_PADDING + INFO_LINES as f32 * LINE_HEIGHT;
+        let mut hovered_entry = None;
+        for (index, definition) in tile_registry().iter().enumerate() {
+            let entry = Rect::new(
+                rect.x + PANEL_PADDING + (index % PALETTE_COLUMNS) as f32 * PALETTE_ENTRY_SIZE,
+                palette_top + (index / PALETTE_COLUMNS) as f32 * PALETTE_ENTRY_SIZE,
+                PALETTE_ENTRY_SIZE,
+                PALETTE_ENTRY_SIZE,
+            );
+            let icon_offset = (PALETTE_ENTRY_SIZE - PALETTE_ICON_SIZE) / 2.0;
+            draw_texture_ex(
+                asset_handle.tile_atlas.0,
+                entry.x + icon_offset,
+                entry.y + icon_offset,
+                WHITE,
+                DrawTextureParams {
+                    dest_size: Some(vec2(PALETTE_ICON_SIZE, PALETTE_ICON_SIZE)),
+                    source: Some(atlas_lookup::tile_rect(&definition.id).into()),
+                    ..Default::default()
+                },
+            );
+            if definition.id == editor.tile {
+                draw_rectangle_lines(entry.x, entry.y, entry.w, entry.h, 2.0, YELLOW);
+            }
+            if entry.contains(mouse_position().into()) {
+                hovered_entry = Some(definition);
+                if actions.is_pressed(Action::Paint) {
+                    editor.tile = definition.id;
+                }
+            }
+        }
+        if let Some(definition) = hovered_entry {
+            draw_tooltip(definition);
+        }
+    }
+}
+
+impl Default for EditorPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Name of a palette tile left of the mouse, as the panel is at the right edge of the screen
+fn draw_tooltip(definition: &TileDefinition) {
+    let (mouse_x, mouse_y) = mouse_position();
+    let size = measure_text(&definition.name, None, FONT_SIZE as u16, 1.0);
+    let left = mouse_x - size.width - PANEL_PADDING * 2.0;
+    draw_rectangle(
+        left - 4.0,
+        mouse_y - size.height - 4.0,
+        size.width + 8.0,
+        size.height + 8.0,
+        BLACK,
+    );
+    draw_text(&definition.name, left, mouse_y, FONT_SIZE, WHITE);
+}
@@ -0,0 +1,33 @@
+use crate::edit_history::EditHistory;
+use crate::viewer::input_map::{Action, ActionState};
+use crate::world::*;
+
+pub const WORLD_SAVE_PATH: &str = "world.wrld";
+pub const REGION_DIRECTORY: &str = "regions";
+pub const STAMP_DIRECTORY: &str = "stamps";
+pub const EDIT_HISTORY_SIZE: usize = 200; // Undoable actions kept
+
+// Saves the world and loads the last save
+pub fn handle_world_saving(world: &mut World, history: &mut EditHistory, actions: &ActionState) {
+    if actions.is_pressed(Action::SaveWorld) && world.region_storage.is_some() {
+        // Region backed worlds only hold part of the world in memory, so write edits to the regions
+        match world.flush_dirty_chunks() {
+            Ok(()) => println!("Saved edited chunks to their region files"),
+            Err(err) => eprintln!("Failed to save chunks to their region files: {}", err),
+        }
+    } else if actions.is_pressed(Action::SaveWorld) {
+        match world.save(WORLD_SAVE_PATH) {
+            Ok(()) => println!("Saved world to {}", WORLD_SAVE_PATH),
+            Err(err) => eprintln!("Failed to save world to {}: {}", WORLD_SAVE_PATH, err),
+        }
+    }
+    if actions.is_pressed(Action::LoadWorld) {
+        match World::load(WORLD_SAVE_PATH) {
+            Ok(loaded_world) => {
+                *world = loaded_world;
+                history.clear(); // The edits were made to the old world
+            }
+            Err(err) => eprintln!("Failed to load world from {}: {}", WORLD_SAVE_PATH, err),
+        }
+    }
+}
@@ -0,0 +1,579 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use macroquad::prelude::*;
+
+use crate::tile_registry::tile_registry;
+use crate::world::*;
+
+// Default bindings, see the file for its layout
+pub const DEFAULT_BINDINGS_TOML: &str = include_str!("../assets/bindings.toml");
+
+// Something the player can do. Game logic asks an ActionState for actions instead of checking
+// keys, the input map decides which keys and buttons trigger them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    PanUp,
+    PanDown,
+    PanLeft,
+    PanRight,
+    PanDrag, // Drags the world along with the mouse
+    ZoomIn,
+    ZoomOut,
+    Paint, // Uses the selected tool, and picks tiles in the editor panel
+    Erase, // Uses the selected tool to clear tiles
+    Undo,
+    Redo,
+    BrushTool,
+    LineTool,
+    RectangleTool,
+    EllipseTool,
+    FloodFillTool,
+    EyedropperTool,
+    SelectTool,
+    BrushSmaller,
+    BrushLarger,
+    PreviousTile,
+    NextTile,
+    PaintTile(TileId), // Selects a tile to paint with
+    CopySelection,
+    PasteStamp,
+    RotateStamp,
+    FlipStampHorizontally,
+    FlipStampVertically,
+    SaveStamp,
+    LoadStamp,
+    TogglePanel,
+    SaveWorld,
+    LoadWorld,
+}
+
+// Every action but PaintTile, by name
+const ACTIONS: &[(&str, Action)] = &[
+    ("PanUp", Action::PanUp),
+    ("PanDown", Action::PanDown),
+    ("PanLeft", Action::PanLeft),
+    ("PanRight", Action::PanRight),
+    ("PanDrag", Action::PanDrag),
+    ("ZoomIn", Action::ZoomIn),
+    ("ZoomOut", Action::ZoomOut),
+    ("Paint", Action::Paint),
+    ("Erase", Action::Erase),
+    ("Undo", Action::Undo),
+    ("Redo", Action::Redo),
+    ("BrushTool", Action::BrushTool),
+    ("LineTool", Action::LineTool),
+    ("RectangleTool", Action::RectangleTool),
+    ("EllipseTool", Action::EllipseTool),
+    ("FloodFillTool", Action::FloodFillTool),
+    ("EyedropperTool", Action::EyedropperTool),
+    ("SelectTool", Action::SelectTool),
+    ("BrushSmaller", Action::BrushSmaller),
+    ("BrushLarger", Action::BrushLarger),
+    ("PreviousTile", Action::PreviousTile),
+    ("NextTile", Action::NextTile),
+    ("CopySelection", Action::CopySelection),
+    ("PasteStamp", Action::PasteStamp),
+    ("RotateStamp", Action::RotateStamp),
+    ("FlipStampHorizontally", Action::FlipStampHorizontally),
+    ("FlipStampVertically", Action::FlipStampVertically),
+    ("SaveStamp", Action::SaveStamp),
+    ("LoadStamp", Action::LoadStamp),
+    ("TogglePanel", Action::TogglePanel),
+    ("SaveWorld", Action::SaveWorld),
+    ("LoadWorld", Action::LoadWorld),
+];
+
+// Parses an action by name. PaintTile takes a tile name or id, like PaintTile(Water).
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let Some(tile) = text
+            .strip_prefix("PaintTile(")
+            .and_then(|text| text.strip_suffix(')'))
+        else {
+            return parse_variant(text, ACTIONS, "action");
+        };
+        let tile = tile.trim();
+        let id = match tile.parse::<u16>() {
+            Ok(id) => Some(TileId(id)).filter(|id| tile_registry().contains(*id)),
+            Err(_) => tile_registry().lookup(tile),
+        };
+        id.map(Action::PaintTile)
+            .ok_or_else(|| format!("unknown tile {}", tile))
+    }
+}
+
+// A key, mouse button or mouse wheel direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    WheelUp,
+    WheelDown,
+}
+
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        // Keys that can be bound, by the name of their key code
+        const KEY_NAMES: &[(&str, KeyCode)] = &[$((stringify!($key), KeyCode::$key)),*];
+    };
+}
+
+key_names!(
+    Space,
+    Apostrophe,
+    Comma,
+    Minus,
+    Period,
+    Slash,
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Semicolon,
+    Equal,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    LeftBracket,
+    Backslash,
+    RightBracket,
+    GraveAccent,
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Insert,
+    Delete,
+    Right,
+    Left,
+    Down,
+    Up,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    CapsLock,
+    ScrollLock,
+    NumLock,
+    PrintScreen,
+    Pause,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    F25,
+    Kp0,
+    Kp1,
+    Kp2,
+    Kp3,
+    Kp4,
+    Kp5,
+    Kp6,
+    Kp7,
+    Kp8,
+    Kp9,
+    KpDecimal,
+    KpDivide,
+    KpMultiply,
+    KpSubtract,
+    KpAdd,
+    KpEnter,
+    KpEqual,
+    LeftShift,
+    LeftControl,
+    LeftAlt,
+    LeftSuper,
+    RightShift,
+    RightControl,
+    RightAlt,
+    RightSuper,
+    Menu,
+);
+
+const MOUSE_INPUTS: &[(&str, Input)] = &[
+    ("MouseLeft", Input::Mouse(MouseButton::Left)),
+    ("MouseRight", Input::Mouse(MouseButton::Right)),
+    ("MouseMiddle", Input::Mouse(MouseButton::Middle)),
+    ("WheelUp", Input::WheelUp),
+    ("WheelDown", Input::WheelDown),
+];
+
+impl Input {
+    // Modifier the input is itself, if it's a modifier key
+    fn modifier(&self) -> Modifiers {
+        let key = match self {
+            Input::Key(key) => *key,
+            _ => return Modifiers::default(),
+        };
+        Modifiers {
+            ctrl: matches!(key, KeyCode::LeftControl | KeyCode::RightControl),
+            shift: matches!(key, KeyCode::LeftShift | KeyCode::RightShift),
+            alt: matches!(key, KeyCode::LeftAlt | KeyCode::RightAlt),
+        }
+    }
+}
+
+impl FromStr for Input {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let inputs: Vec<(&str, Input)> = MOUSE_INPUTS
+            .iter()
+            .copied()
+            .chain(
+                KEY_NAMES
+                    .iter()
+                    .map(|(name, key)| (*name, Input::Key(*key))),
+            )
+            .collect();
+        parse_variant(text, &inputs, "key or mouse input")
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Input::Key(key) => KEY_NAMES
+                .iter()
+                .find(|(_, named_key)| named_key == key)
+                .map_or("Unknown", |(name, _)| name),
+            input => MOUSE_INPUTS
+                .iter()
+                .find(|(_, named_input)| named_input == input)
+                .map_or("Unknown", |(name, _)| name),
+        };
+        formatter.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+// An input that triggers an action while exactly its modifiers are held
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub input: Input,
+    pub modifiers: Modifiers,
+}
+
+// Parses a binding like Ctrl+Shift+Z
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let input = parts.pop().unwrap_or_default().parse()?;
+        let mut modifiers = Modifiers::default();
+        for part in parts {
+            let modifier = match part.to_lowercase().as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "shift" => &mut modifiers.shift,
+                "alt" => &mut modifiers.alt,
+                _ => {
+                    return Err(format!(
+                        "unknown modifier {}, expected one of: Ctrl, Shift, Alt",
+                        part
+                    ))
+                }
+            };
+            *modifier = true;
+        }
+        Ok(Binding { input, modifiers })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for (held, name) in [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.shift, "Shift"),
+            (self.modifiers.alt, "Alt"),
+        ] {
+            if held {
+                write!(formatter, "{}+", name)?;
+            }
+        }
+        write!(formatter, "{}", self.input)
+    }
+}
+
+// State of the keyboard and mouse in one frame. Captured from macroquad while the viewer runs,
+// and built by hand to replay input without a window.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputSnapshot {
+    pub down: HashSet<Input>,
+    pub pressed: HashSet<Input>, // Inputs that went down this frame
+    pub wheel: f32,              // How far the mouse wheel moved this frame, up is positive
+}
+
+impl InputSnapshot {
+    // A frame where the inputs were just pressed
+    pub fn pressing(inputs: &[Input]) -> Self {
+        InputSnapshot {
+            down: inputs.iter().copied().collect(),
+            pressed: inputs.iter().copied().collect(),
+            wheel: 0.0,
+        }
+    }
+
+    // A frame where the inputs are held, after being pressed in an earlier frame
+    pub fn holding(inputs: &[Input]) -> Self {
+        InputSnapshot {
+            down: inputs.iter().copied().collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_down(&self, input: Input) -> bool {
+        match input {
+            Input::WheelUp => self.wheel > 0.0,
+            Input::WheelDown => self.wheel < 0.0,
+            input => self.down.contains(&input),
+        }
+    }
+
+    pub fn is_pressed(&self, input: Input) -> bool {
+        match input {
+            Input::WheelUp | Input::WheelDown => self.is_down(input),
+            input => self.pressed.contains(&input),
+        }
+    }
+
+    // How far an input is held: the distance the wheel moved, or 1 for keys and buttons
+    pub fn value(&self, input: Input) -> f32 {
+        match input {
+            _ if !self.is_down(input) => 0.0,
+            Input::WheelUp | Input::WheelDown => self.wheel.abs(),
+            _ => 1.0,
+        }
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        let held =
+            |keys: [KeyCode; 2]| keys.iter().any(|key| self.down.contains(&Input::Key(*key)));
+        Modifiers {
+            ctrl: held([KeyCode::LeftControl, KeyCode::RightControl]),
+            shift: held([KeyCode::LeftShift, KeyCode::RightShift]),
+            alt: held([KeyCode::LeftAlt, KeyCode::RightAlt]),
+        }
+    }
+}
+
+// Actions triggered in one frame, what game logic reads input from
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ActionState {
+    values: HashMap<Action, f32>, // Held actions, by how far they are held
+    pressed: HashSet<Action>,     // Actions that started this frame
+}
+
+impl ActionState {
+    pub fn is_down(&self, action: Action) -> bool {
+        self.values.contains_key(&action)
+    }
+
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    // How far an action is held: the distance the mouse wheel moved for wheel bindings, 1 for
+    // keys and buttons and 0 when it isn't held
+    pub fn value(&self, action: Action) -> f32 {
+        self.values.get(&action).copied().unwrap_or(0.0)
+    }
+
+    pub fn pressed_actions(&self) -> impl Iterator<Item = Action> + '_ {
+        self.pressed.iter().copied()
+    }
+
+    // Holds an action with a value of 1, for driving game logic without an input map
+    pub fn hold(&mut self, action: Action) {
+        self.hold_by(action, 1.0);
+    }
+
+    pub fn hold_by(&mut self, action: Action, value: f32) {
+        let held_value = self.values.entry(action).or_default();
+        *held_value = held_value.max(value);
+    }
+
+    // Starts an action this frame
+    pub fn press(&mut self, action: Action) {
+        self.hold(action);
+        self.pressed.insert(action);
+    }
+}
+
+// Bindings of every action
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InputMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+}
+
+impl InputMap {
+    // Map with the default bindings
+    pub fn builtin() -> Self {
+        let mut input_map = InputMap::default();
+        input_map
+            .add_from_toml_str(DEFAULT_BINDINGS_TOML)
+            .expect("default bindings are valid");
+        input_map
+    }
+
+    // Rebinds the actions of a bindings file
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), String> {
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        self.add_from_toml_str(&text)
+    }
+
+    // Rebinds every action in a bindings file's text, or none of them if one is invalid
+    pub fn add_from_toml_str(&mut self, text: &str) -> Result<(), String> {
+        let file: BTreeMap<String, Vec<String>> =
+            toml::from_str(text).map_err(|err| err.to_string())?;
+        let mut bindings = self.bindings.clone();
+        for (action_name, binding_names) in file {
+            let action = action_name.parse()?;
+            let action_bindings = binding_names
+                .iter()
+                .map(|binding| {
+                    binding
+                        .parse()
+                        .map_err(|err| format!("{} binding {}: {}", action_name, binding, err))
+                })
+                .collect::<Result<Vec<Binding>, String>>()?;
+            bindings.insert(action, action_bindings);
+        }
+        self.bindings = bindings;
+        Ok(())
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    // Replaces the bindings of an action
+    pub fn bind(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.bindings.insert(action, bindings);
+    }
+
+    // Reads the state of every bound input, and the modifier keys, from macroquad
+    pub fn capture(&self) -> InputSnapshot {
+        let modifier_keys = [
+            KeyCode::LeftControl,
+            KeyCode::RightControl,
+            KeyCode::LeftShift,
+            KeyCode::RightShift,
+            KeyCode::LeftAlt,
+            KeyCode::RightAlt,
+        ];
+        let inputs = self
+            .bindings
+            .values()
+            .flatten()
+            .map(|binding| binding.input)
+            .chain(modifier_keys.map(Input::Key));
+        let mut snapshot = InputSnapshot {
+            wheel: mouse_wheel().1,
+            ..Default::default()
+        };
+        for input in inputs {
+            let (down, pressed) = match input {
+                Input::Key(key) => (is_key_down(key), is_key_pressed(key)),
+                Input::Mouse(button) => (
+                    is_mouse_button_down(button),
+                    is_mouse_button_pressed(button),
+                ),
+                Input::WheelUp | Input::WheelDown => continue,
+            };
+            if down {
+                snapshot.down.insert(input);
+            }
+            if pressed {
+                snapshot.pressed.insert(input);
+            }
+        }
+        snapshot
+    }
+
+    // Actions triggered by the inputs of a frame
+    pub fn actions(&self, snapshot: &InputSnapshot) -> ActionState {
+        let held_modifiers = snapshot.modifiers();
+        let mut actions = ActionState::default();
+        for (action, bindings) in &self.bindings {
+            for binding in bindings {
+                // Binding a modifier key itself works, even though it is then held
+                let own_modifier = binding.input.modifier();
+                let modifiers = Modifiers {
+                    ctrl: held_modifiers.ctrl && !own_modifier.ctrl,
+                    shift: held_modifiers.shift && !own_modifier.shift,
+                    alt: held_modifiers.alt && !own_modifier.alt,
+                };
+                if modifiers != binding.modifiers || !snapshot.is_down(binding.input) {
+                    continue;
+                }
+                actions.hold_by(*action, snapshot.value(binding.input));
+                if snapshot.is_pressed(binding.input) {
+                    actions.pressed.insert(*action);
+                }
+            }
+        }
+        actions
+    }
+}
@@ -0,0 +1,107 @@
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+use crate::assets::atlas_lookup::TILE_SIZE;
+use crate::viewer::camera::camera_view_rect;
+use crate::world::*;
+
+// Zoomed out far enough that a tile covers at most this many pixels, chunks are only drawn as
+// their low detail texture
+pub const LOD_FULL_TILE_PIXELS: f32 = 4.0;
+// Zoomed in far enough that a tile covers at least this many pixels, only sprites are drawn. In
+// between the two, the low detail textures fade in over the sprites.
+pub const LOD_NONE_TILE_PIXELS: f32 = 8.0;
+
+// Low detail textures of the chunks on screen, one pixel per tile in the tile's map color
+#[derive(Default)]
+pub struct ChunkLodCache {
+    textures: HashMap<ChunkPos, (u64, Texture2D)>, // Revision of the chunk the texture was built from
+}
+
+impl ChunkLodCache {
+    pub fn new() -> Self {
+        ChunkLodCache::default()
+    }
+
+    // Returns the texture for a chunk, updating it if the chunk changed since it was built
+    fn get_texture(&mut self, chunk_pos: &ChunkPos, chunk: &Chunk) -> Texture2D {
+        if let Some((revision, texture)) = self.textures.get_mut(chunk_pos) {
+            if *revision != chunk.revision() {
+                *revision = chunk.revision();
+                texture.update(&chunk_lod_image(chunk));
+            }
+            return *texture;
+        }
+        let texture = Texture2D::from_image(&chunk_lod_image(chunk));
+        texture.set_filter(FilterMode::Nearest);
+        self.textures
+            .insert(*chunk_pos, (chunk.revision(), texture));
+        texture
+    }
+
+    // Frees the textures of chunks outside the view
+    fn retain_visible(&mut self, view: &ViewRect) {
+        self.textures.retain(|chunk_pos, (_, texture)| {
+            let visible = view.contains_chunk(chunk_pos);
+            if !visible {
+                // Textures live on the gpu until they are deleted
+                texture.delete();
+            }
+            visible
+        });
+    }
+}
+
+// Image of a chunk with one pixel per tile in the color of its uppermost layer, rows going down
+// the chunk
+fn chunk_lod_image(chunk: &Chunk) -> Image {
+    let size = CHUNK_SIZE as u16;
+    let bytes = (0..TILES_PER_CHUNK)
+        .flat_map(|local_index| chunk.top_tile(local_index).map_color())
+        .collect();
+    Image {
+        bytes,
+        width: size,
+        height: size,
+    }
+}
+
+// How much of the low detail textures to draw at the camera's zoom, from 0 (only sprites) to 1
+// (only low detail textures)
+pub fn lod_weight(camera: &Camera2D) -> f32 {
+    // Camera zoom maps world units to half the screen
+    let tile_pixels = TILE_SIZE * camera.zoom.x * screen_width() / 2.0;
+    let weight =
+        (LOD_NONE_TILE_PIXELS - tile_pixels) / (LOD_NONE_TILE_PIXELS - LOD_FULL_TILE_PIXELS);
+    weight.clamp(0.0, 1.0)
+}
+
+// Low detail rendering
+impl World {
+    // Renders the chunks visible to the camera as one textured quad each, see lod_weight
+    pub fn render_visible_chunk_lods(
+        &self,
+        camera: &Camera2D,
+        lod_cache: &mut ChunkLodCache,
+        weight: f32,
+    ) {
+        let view = camera_view_rect(camera);
+        lod_cache.retain_visible(&view);
+
+        let chunk_world_size = CHUNK_SIZE as f32 * TILE_SIZE;
+        for (chunk_pos, chunk) in self.visible_chunks(&view) {
+            let texture = lod_cache.get_texture(&chunk_pos, chunk);
+            draw_texture_ex(
+                texture,
+                chunk_pos.x as f32 * chunk_world_size,
+                -chunk_pos.y as f32 * chunk_world_size - chunk_world_size,
+                Color::new(1.0, 1.0, 1.0, weight),
+                DrawTextureParams {
+                    dest_size: Some(Vec2::new(chunk_world_size, chunk_world_size)),
+                    flip_y: true,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,9 @@
+// Rendering and input for viewing and editing worlds with macroquad
+pub mod assets;
+pub mod camera;
+pub mod editor;
+pub mod editor_panel;
+pub mod input;
+pub mod input_map;
+pub mod lod;
+pub mod rendering;
@@ -0,0 +1,255 @@
+use macroquad::models::{draw_mesh, Mesh, Vertex};
+use macroquad::prelude::*;
+use std::collections::HashMap;
+
+use crate::animation::{tile_animation, TileAnimation};
+use crate::assets::atlas_lookup::{self, TILE_SIZE};
+use crate::assets::AtlasRect;
+use crate::autotile::{NEIGHBOR_ALL, TRANSITION_SPRITES};
+use crate::viewer::assets::AssetHandle;
+use crate::viewer::camera::camera_view_rect;
+use crate::world::*;
+
+// Most quads a chunk mesh can have: a terrain tile, a transition from every side and a tile in
+// every overlay layer per spot
+pub const MAX_CHUNK_MESH_QUADS: usize =
+    TILES_PER_CHUNK * (1 + TRANSITION_SPRITES as usize + TileLayer::OVERLAYS.len());
+const DEFAULT_DRAWCALL_VERTICES: usize = 10000; // What macroquad starts out with
+
+// Revisions of a chunk and of the four chunks next to it, tiles on the chunk's border autotile
+// with tiles in the neighboring chunks. None for chunks that aren't loaded.
+type MeshRevisions = [Option<u64>; 5];
+
+// Meshes of the chunks on screen, each holding a textured quad per tile so a chunk is drawn with
+// a single draw call. A mesh is only rebuilt when its chunk or one of its neighbors changes.
+#[derive(Default)]
+pub struct ChunkMeshCache {
+    meshes: HashMap<ChunkPos, ChunkMesh>,
+}
+
+struct ChunkMesh {
+    revisions: MeshRevisions, // What the mesh was built from
+    mesh: Mesh,
+    animated_tiles: Vec<AnimatedTile>, // Their uvs are updated every frame instead of rebuilding the mesh
+}
+
+// Quad of an animated tile in a chunk mesh
+struct AnimatedTile {
+    first_vertex: usize,
+    pos: GlobalTilePos,
+    tile: TileId,
+    sprite: u8, // Neighbor mask or transition sprite
+    animation: &'static TileAnimation,
+    frame: usize, // Animation frame the quad's uvs show
+}
+
+impl ChunkMeshCache {
+    pub fn new() -> Self {
+        ChunkMeshCache::default()
+    }
+
+    // Returns the mesh for a chunk with animated tiles showing their frame at a time in seconds,
+    // rebuilding the mesh if the chunk changed since it was built
+    fn get_mesh(
+        &mut self,
+        world: &World,
+        chunk_pos: &ChunkPos,
+        chunk: &Chunk,
+        asset_handle: &AssetHandle,
+        time: f32,
+    ) -> &Mesh {
+        let revisions = mesh_revisions(world, chunk_pos);
+        let chunk_mesh = self
+            .meshes
+            .entry(*chunk_pos)
+            .or_insert_with(|| build_chunk_mesh(world, chunk_pos, chunk, asset_handle));
+        if chunk_mesh.revisions != revisions {
+            *chunk_mesh = build_chunk_mesh(world, chunk_pos, chunk, asset_handle);
+        }
+        chunk_mesh.animate(time, &asset_handle.autotile_atlas.0);
+        &chunk_mesh.mesh
+    }
+}
+
+impl ChunkMesh {
+    // Points the uvs of animated tiles at their current frame
+    fn animate(&mut self, time: f32, texture: &Texture2D) {
+        for animated_tile in &mut self.animated_tiles {
+            let frame = animated_tile.animation.frame_at(time, &animated_tile.pos);
+            if frame == animated_tile.frame {
+                continue;
+            }
+            animated_tile.frame = frame;
+            let source =
+                atlas_lookup::autotile_rect(&animated_tile.tile, animated_tile.sprite, frame);
+            let vertices = &mut self.mesh.vertices[animated_tile.first_vertex..][..4];
+            for (vertex, uv) in vertices.iter_mut().zip(quad_uvs(&source, texture)) {
+                vertex.uv = uv;
+            }
+        }
+    }
+}
+
+fn mesh_revisions(world: &World, chunk_pos: &ChunkPos) -> MeshRevisions {
+    let (x, y) = (chunk_pos.x, chunk_pos.y);
+    [(x, y), (x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)].map(|(x, y)| {
+        world
+            .chunks
+            .get(&ChunkPos { x, y })
+            .map(|chunk| chunk.revision())
+    })
+}
+
+fn vertex(x: f32, y: f32, uv: Vec2) -> Vertex {
+    Vertex {
+        position: vec3(x, y, 0.0),
+        uv,
+        color: WHITE,
+    }
+}
+
+// Uvs of a quad's top left, top right, bottom right and bottom left corners
+fn quad_uvs(source: &AtlasRect, texture: &Texture2D) -> [Vec2; 4] {
+    let (u0, v0) = (source.x / texture.width(), source.y / texture.height());
+    let (u1, v1) = (
+        (source.x + source.w) / texture.width(),
+        (source.y + source.h) / texture.height(),
+    );
+    [vec2(u0, v0), vec2(u1, v0), vec2(u1, v1), vec2(u0, v1)]
+}
+
+// Builds a mesh with a quad for every tile in the chunk, in world space, layer by layer so
+// decorations and objects are drawn over the terrain. Terrain tiles use the autotile sprite
+// matching their neighbors, with the transitions from neighbors of other types drawn over them.
+fn build_chunk_mesh(
+    world: &World,
+    chunk_pos: &ChunkPos,
+    chunk: &Chunk,
+    asset_handle: &AssetHandle,
+) -> ChunkMesh {
+    let mut builder = ChunkMeshBuilder {
+        texture: asset_handle.autotile_atlas.0,
+        vertices: Vec::with_capacity(TILES_PER_CHUNK * 4),
+        indices: Vec::with_capacity(TILES_PER_CHUNK * 6),
+        animated_tiles: Vec::new(),
+    };
+    for (global_pos, tile) in chunk.iter_tiles(chunk_pos) {
+        builder.add_tile(global_pos, *tile, world.neighbor_mask(&global_pos));
+        for (sprite, neighbor) in world.transitions(&global_pos) {
+            builder.add_tile(global_pos, neighbor, sprite);
+        }
+    }
+    for overlay in TileLayer::OVERLAYS
+        .map(|layer| chunk.overlay(layer))
+        .iter()
+        .flatten()
+    {
+        for (local_index, tile) in overlay.iter() {
+            builder.add_tile(chunk_pos.tile_pos(local_index), tile, NEIGHBOR_ALL);
+        }
+    }
+    ChunkMesh {
+        revisions: mesh_revisions(world, chunk_pos),
+        mesh: Mesh {
+            vertices: builder.vertices,
+            indices: builder.indices,
+            texture: Some(builder.texture),
+        },
+        animated_tiles: builder.animated_tiles,
+    }
+}
+
+struct ChunkMeshBuilder {
+    texture: Texture2D,
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+    animated_tiles: Vec<AnimatedTile>,
+}
+
+impl ChunkMeshBuilder {
+    // Adds a quad showing one of a tile's autotile sprites at a position
+    fn add_tile(&mut self, global_pos: GlobalTilePos, tile: TileId, sprite: u8) {
+        let [top_left_uv, top_right_uv, bottom_right_uv, bottom_left_uv] = quad_uvs(
+            &atlas_lookup::autotile_rect(&tile, sprite, 0),
+            &self.texture,
+        );
+        // World space y points up, so the top of the sprite goes at the larger y
+        let left = global_pos.0 as f32 * TILE_SIZE;
+        let top = -global_pos.1 as f32 * TILE_SIZE;
+        let right = left + TILE_SIZE;
+        let bottom = top - TILE_SIZE;
+
+        if let Some(animation) = tile_animation(&tile) {
+            self.animated_tiles.push(AnimatedTile {
+                first_vertex: self.vertices.len(),
+                pos: global_pos,
+                tile,
+                sprite,
+                animation,
+                frame: 0,
+            });
+        }
+        let first_vertex = self.vertices.len() as u16;
+        self.vertices.extend([
+            vertex(left, top, top_left_uv),
+            vertex(right, top, top_right_uv),
+            vertex(right, bottom, bottom_right_uv),
+            vertex(left, bottom, bottom_left_uv),
+        ]);
+        self.indices
+            .extend([0, 1, 2, 0, 2, 3].map(|index| first_vertex + index));
+    }
+}
+
+// Makes macroquad's draw call buffers large enough for the biggest chunk mesh. By default they
+// hold 5000 indices, and the part of a mesh that doesn't fit is silently not drawn.
+pub fn reserve_chunk_mesh_capacity() {
+    gl_set_drawcall_buffer_capacity(
+        (MAX_CHUNK_MESH_QUADS * 4).max(DEFAULT_DRAWCALL_VERTICES),
+        MAX_CHUNK_MESH_QUADS * 6,
+    );
+}
+
+// Rendering for tiles
+impl World {
+    // Renders the chunks visible to the camera, one mesh per chunk
+    pub fn render_visible_chunks(
+        &self,
+        camera: &Camera2D,
+        asset_handle: &AssetHandle,
+        mesh_cache: &mut ChunkMeshCache,
+    ) {
+        let view = camera_view_rect(camera);
+        // Forget meshes that went off screen, they are cheap to rebuild
+        mesh_cache
+            .meshes
+            .retain(|chunk_pos, _| view.contains_chunk(chunk_pos));
+
+        let time = get_time() as f32;
+        for (chunk_pos, chunk) in self.visible_chunks(&view) {
+            draw_mesh(mesh_cache.get_mesh(self, &chunk_pos, chunk, asset_handle, time));
+        }
+    }
+}
+
+pub fn _render_entire_world(world: &World, asset_handle: &AssetHandle) {
+    for (chunk_pos, chunk) in world.chunks.iter() {
+        for y in 0..16 {
+            for x in 0..16 {
+                draw_texture_ex(
+                    asset_handle.tile_atlas.0,
+                    { (chunk_pos.x as f32 * TILE_SIZE * 16.0) + x as f32 * TILE_SIZE }.round(),
+                    { (-chunk_pos.y as f32 * TILE_SIZE * 16.0) - y as f32 * TILE_SIZE }.round()
+                        - TILE_SIZE,
+                    WHITE,
+                    DrawTextureParams {
+                        dest_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
+                        source: Some(atlas_lookup::tile_rect(chunk.tiles.get(x + y * 16)).into()),
+                        flip_y: true,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
+}
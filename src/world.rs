@@ -1,47 +1,256 @@
 #![allow(dead_code)]
 
-use crate::{
-    assets::{atlas_lookup::TILE_SIZE, AssetHandle},
-    utils::get_atlas_rect,
-};
-use macroquad::prelude::*;
-use std::collections::HashMap;
-
-#[derive(PartialEq, Eq, Hash, Debug, Clone)]
+use crate::chunk_pipeline::ChunkGenerationPipeline;
+use crate::region::RegionStorage;
+use crate::world_generation::PerlinChunkGenerator;
+
+use crate::tile_metadata::ChunkMetadata;
+use crate::tile_registry::tile_registry;
+pub use crate::tile_registry::TileId;
+use noise::{Fbm, Perlin};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
 pub struct ChunkPos {
     pub x: i32,
     pub y: i32,
 }
 
+// Tiles of a chunk, row by row, in layers. Every layer always has room for exactly
+// TILES_PER_CHUNK tiles, so every local index from GlobalTilePos::to_chunk_local is in bounds.
 #[derive(Debug, Clone)]
-pub enum Tile {
-    Grass,
-    Stone,
-    Sand,
-    Water,
-    ShallowWater,
-    DeepWater,
-    DarkStone,
-    Snow,
+pub struct Chunk {
+    pub tiles: TerrainTiles, // Terrain layer
+    pub decorations: OverlayTiles,
+    pub objects: OverlayTiles,
+    pub metadata: ChunkMetadata,
+    revision: u64,
 }
 
-#[derive(Debug, Clone)]
-pub struct Chunk {
-    pub tiles: Vec<Tile>,
+// Layers of a chunk, stored and drawn in this order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Deserialize)]
+pub enum TileLayer {
+    #[default]
+    Terrain, // The ground, there is a terrain tile everywhere
+    Decoration, // Small things lying on the ground, like flowers and rocks
+    Object,     // Things standing on the ground, like trees and buildings
+}
+
+impl TileLayer {
+    pub const ALL: [TileLayer; 3] = [TileLayer::Terrain, TileLayer::Decoration, TileLayer::Object];
+    pub const OVERLAYS: [TileLayer; 2] = [TileLayer::Decoration, TileLayer::Object];
+
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<TileLayer> {
+        match id {
+            0 => Some(TileLayer::Terrain),
+            1 => Some(TileLayer::Decoration),
+            2 => Some(TileLayer::Object),
+            _ => None,
+        }
+    }
+}
+
+// Terrain tiles of a chunk. A chunk of a single tile, like open water, only stores that tile. The
+// full array is allocated when the chunk gets a second tile, and dropped again once every tile is
+// the same.
+#[derive(Debug, Clone, Eq)]
+pub enum TerrainTiles {
+    Uniform(TileId),
+    Mixed(Box<[TileId; TILES_PER_CHUNK]>),
+}
+
+impl TerrainTiles {
+    pub fn new(tiles: [TileId; TILES_PER_CHUNK]) -> Self {
+        if tiles.iter().all(|tile| *tile == tiles[0]) {
+            TerrainTiles::Uniform(tiles[0])
+        } else {
+            TerrainTiles::Mixed(Box::new(tiles))
+        }
+    }
+
+    pub fn get(&self, local_index: usize) -> &TileId {
+        match self {
+            TerrainTiles::Uniform(tile) => tile,
+            TerrainTiles::Mixed(tiles) => &tiles[local_index],
+        }
+    }
+
+    pub fn set(&mut self, local_index: usize, tile: TileId) {
+        match self {
+            TerrainTiles::Uniform(uniform_tile) if *uniform_tile == tile => (),
+            TerrainTiles::Uniform(uniform_tile) => {
+                let mut tiles = Box::new([*uniform_tile; TILES_PER_CHUNK]);
+                tiles[local_index] = tile;
+                *self = TerrainTiles::Mixed(tiles);
+            }
+            TerrainTiles::Mixed(tiles) => {
+                tiles[local_index] = tile;
+                if tiles.iter().all(|other| *other == tile) {
+                    *self = TerrainTiles::Uniform(tile);
+                }
+            }
+        }
+    }
+
+    // Iterates over the tiles by local index
+    pub fn iter(&self) -> impl Iterator<Item = &TileId> + '_ {
+        (0..TILES_PER_CHUNK).map(|local_index| self.get(local_index))
+    }
+
+    pub fn to_array(&self) -> [TileId; TILES_PER_CHUNK] {
+        std::array::from_fn(|local_index| *self.get(local_index))
+    }
+}
+
+// Chunks with the same tiles are equal however they are stored
+impl PartialEq for TerrainTiles {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+// Tiles of a layer above the terrain, where most spots are empty. Nothing is allocated until the
+// layer gets its first tile.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OverlayTiles {
+    tiles: Option<Box<[Option<TileId>; TILES_PER_CHUNK]>>,
+}
+
+impl OverlayTiles {
+    pub fn get(&self, local_index: usize) -> Option<TileId> {
+        self.tiles.as_ref()?[local_index]
+    }
+
+    pub fn set(&mut self, local_index: usize, tile: Option<TileId>) {
+        if tile.is_none() && self.tiles.is_none() {
+            return;
+        }
+        self.tiles
+            .get_or_insert_with(|| Box::new([None; TILES_PER_CHUNK]))[local_index] = tile;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    // Iterates over the spots that have a tile, by local index
+    pub fn iter(&self) -> impl Iterator<Item = (usize, TileId)> + '_ {
+        self.tiles
+            .iter()
+            .flat_map(|tiles| tiles.iter().enumerate())
+            .filter_map(|(local_index, tile)| Some((local_index, (*tile)?)))
+    }
+}
+
+// Source of chunk revisions, shared by every world so no two chunks ever get the same revision
+static NEXT_CHUNK_REVISION: AtomicU64 = AtomicU64::new(0);
+
+impl Chunk {
+    pub fn new(tiles: [TileId; TILES_PER_CHUNK]) -> Self {
+        Chunk {
+            tiles: TerrainTiles::new(tiles),
+            decorations: OverlayTiles::default(),
+            objects: OverlayTiles::default(),
+            metadata: ChunkMetadata::default(),
+            revision: NEXT_CHUNK_REVISION.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+
+    // Chunk of a single tile
+    pub fn filled(tile: TileId) -> Self {
+        Chunk::new([tile; TILES_PER_CHUNK])
+    }
+
+    // Changes whenever the chunk is edited or replaced, so anything built from the chunk's tiles,
+    // like its mesh, can tell when it needs rebuilding. Edits through World::set_layer_tile
+    // are tracked, code changing tiles directly has to call mark_changed.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn mark_changed(&mut self) {
+        self.revision = NEXT_CHUNK_REVISION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Tile of a layer at a local index, None where an overlay layer is empty
+    pub fn layer_tile(&self, layer: TileLayer, local_index: usize) -> Option<TileId> {
+        match self.overlay(layer) {
+            Some(overlay) => overlay.get(local_index),
+            None => Some(*self.tiles.get(local_index)),
+        }
+    }
+
+    // Tiles of a layer above the terrain, None for the terrain layer
+    pub fn overlay(&self, layer: TileLayer) -> Option<&OverlayTiles> {
+        match layer {
+            TileLayer::Terrain => None,
+            TileLayer::Decoration => Some(&self.decorations),
+            TileLayer::Object => Some(&self.objects),
+        }
+    }
+
+    pub fn overlay_mut(&mut self, layer: TileLayer) -> Option<&mut OverlayTiles> {
+        match layer {
+            TileLayer::Terrain => None,
+            TileLayer::Decoration => Some(&mut self.decorations),
+            TileLayer::Object => Some(&mut self.objects),
+        }
+    }
+
+    // The uppermost tile at a local index, what the spot looks like from above
+    pub fn top_tile(&self, local_index: usize) -> TileId {
+        TileLayer::ALL
+            .iter()
+            .rev()
+            .find_map(|layer| self.layer_tile(*layer, local_index))
+            .unwrap_or(*self.tiles.get(local_index))
+    }
+
+    // Iterates over the chunk's terrain tiles with their global positions, given where the chunk is
+    pub fn iter_tiles<'a>(
+        &'a self,
+        chunk_pos: &ChunkPos,
+    ) -> impl Iterator<Item = (GlobalTilePos, &'a TileId)> + 'a {
+        let chunk_pos = *chunk_pos;
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(move |(local_index, tile)| (chunk_pos.tile_pos(local_index), tile))
+    }
 }
 
 pub struct World {
     pub chunks: HashMap<ChunkPos, Chunk>,
+    pub seed: u32,
+    pub generation_type: Option<WorldGenerationType>, // None for worlds that were never generated
+    pub island_size: Option<WorldIslandSize>,
+    pub perlin_settings: PerlinWorldSettings,
+    pub chunk_generator: Option<Arc<PerlinChunkGenerator>>, // Generates missing chunks on demand
+    pub chunk_pipeline: Option<ChunkGenerationPipeline>, // When set, chunks are generated on worker threads
+
+    pub region_storage: Option<RegionStorage>, // When set, chunks are paged in and out of region files
+    pub dirty_chunks: HashSet<ChunkPos>, // Chunks edited or generated since they were last written to region files
 }
 
 #[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum WorldGenerationType {
     WaterWorld,
     ChunkMess,
     TileMess,
     PerlinTerrain,
+    InfinitePerlinTerrain,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum WorldGenerationSize {
     Tiny = 2,
     Small = 5,
@@ -51,7 +260,7 @@ pub enum WorldGenerationSize {
     Titanic = 125,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 pub enum WorldIslandSize {
     Tiny = 2,
     Small = 4,
@@ -61,37 +270,436 @@ pub enum WorldIslandSize {
     Titanic = 15,
 }
 
+// Parses an enum variant by name, ignoring case, dashes and underscores
+pub(crate) fn parse_variant<T: Copy>(
+    text: &str,
+    variants: &[(&str, T)],
+    kind: &str,
+) -> Result<T, String> {
+    let normalize = |name: &str| name.replace(['-', '_'], "").to_lowercase();
+    let text = normalize(text);
+    for (name, variant) in variants {
+        if normalize(name) == text {
+            return Ok(*variant);
+        }
+    }
+    let names: Vec<&str> = variants.iter().map(|(name, _)| *name).collect();
+    Err(format!(
+        "unknown {}, expected one of: {}",
+        kind,
+        names.join(", ")
+    ))
+}
+
+impl FromStr for WorldGenerationType {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse_variant(
+            text,
+            &[
+                ("WaterWorld", WorldGenerationType::WaterWorld),
+                ("ChunkMess", WorldGenerationType::ChunkMess),
+                ("TileMess", WorldGenerationType::TileMess),
+                ("PerlinTerrain", WorldGenerationType::PerlinTerrain),
+                (
+                    "InfinitePerlinTerrain",
+                    WorldGenerationType::InfinitePerlinTerrain,
+                ),
+            ],
+            "generation type",
+        )
+    }
+}
+
+impl FromStr for WorldGenerationSize {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse_variant(
+            text,
+            &[
+                ("Tiny", WorldGenerationSize::Tiny),
+                ("Small", WorldGenerationSize::Small),
+                ("Medium", WorldGenerationSize::Medium),
+                ("Large", WorldGenerationSize::Large),
+                ("Huge", WorldGenerationSize::Huge),
+                ("Titanic", WorldGenerationSize::Titanic),
+            ],
+            "world size",
+        )
+    }
+}
+
+impl FromStr for TileLayer {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse_variant(
+            text,
+            &[
+                ("Terrain", TileLayer::Terrain),
+                ("Decoration", TileLayer::Decoration),
+                ("Object", TileLayer::Object),
+            ],
+            "tile layer",
+        )
+    }
+}
+
+impl FromStr for WorldIslandSize {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse_variant(
+            text,
+            &[
+                ("Tiny", WorldIslandSize::Tiny),
+                ("Small", WorldIslandSize::Small),
+                ("Medium", WorldIslandSize::Medium),
+                ("Large", WorldIslandSize::Large),
+                ("Huge", WorldIslandSize::Huge),
+                ("Titanic", WorldIslandSize::Titanic),
+            ],
+            "island size",
+        )
+    }
+}
+
+// Settings for perlin terrain generation
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct PerlinWorldSettings {
-    pub water_level: f64,
-    pub sand_level: f64,
-    pub grass_level: f64,
-    pub stone_level: f64,
+    // Tiles by height, sorted from the lowest band to the highest
+    pub height_bands: Vec<HeightBand>,
 
-    pub height_scale_factor: f64,
+    // Decoration and object tiles scattered over the terrain, applied in order
+    pub decorations: Vec<DecorationRule>,
+
+    pub height_scale_factor: f64, // Noise values are multiplied by this before being compared to the bands
+
+    // Fbm noise parameters
+    pub octaves: usize,
+    pub frequency: f64,
+    pub lacunarity: f64,
+    pub persistence: f64,
+}
+
+// A tile used from min_height up to the next band. Heights below the first band use its tile.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HeightBand {
+    pub min_height: f64,
+    pub tile: TileId,
+}
+
+// Places a decoration or object tile on about a fraction (density, from 0 to 1) of the tiles of
+// one terrain tile type, skipping spots where its layer already has a tile
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DecorationRule {
+    pub tile: TileId,
+    pub on: TileId,
+    pub density: f64,
+}
+
+impl Default for PerlinWorldSettings {
+    fn default() -> Self {
+        let terrain_height_offset = 0.4;
+        let band = |min_height, tile| HeightBand { min_height, tile };
+        let decoration = |tile, on, density| DecorationRule { tile, on, density };
+        PerlinWorldSettings {
+            height_bands: vec![
+                // Water
+                band(f64::NEG_INFINITY, TileId::DEEP_WATER),
+                band(-1.55 + terrain_height_offset, TileId::WATER),
+                band(-0.50 + terrain_height_offset, TileId::SHALLOW_WATER),
+                // Land
+                band(0.0 + terrain_height_offset, TileId::SAND),
+                band(0.29 + terrain_height_offset, TileId::GRASS),
+                band(0.8 + terrain_height_offset, TileId::STONE),
+                band(1.1 + terrain_height_offset, TileId::DARK_STONE),
+                band(1.5 + terrain_height_offset, TileId::SNOW),
+            ],
+            decorations: vec![
+                decoration(TileId::TREE, TileId::GRASS, 0.04),
+                decoration(TileId::FLOWERS, TileId::GRASS, 0.06),
+                decoration(TileId::ROCK, TileId::SAND, 0.03),
+            ],
+
+            height_scale_factor: 1.9,
+
+            octaves: Fbm::<Perlin>::DEFAULT_OCTAVE_COUNT,
+            frequency: Fbm::<Perlin>::DEFAULT_FREQUENCY,
+            lacunarity: Fbm::<Perlin>::DEFAULT_LACUNARITY,
+            persistence: Fbm::<Perlin>::DEFAULT_PERSISTENCE,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq)]
+impl PerlinWorldSettings {
+    // Returns the tile for a scaled noise height
+    pub fn tile_at_height(&self, height: f64) -> TileId {
+        match self
+            .height_bands
+            .iter()
+            .rev()
+            .find(|band| height >= band.min_height)
+        {
+            Some(band) => band.tile,
+            None => self.height_bands[0].tile,
+        }
+    }
+
+    // Checks that the settings can be used to generate a world
+    pub fn validate(&self) -> Result<(), String> {
+        if self.height_bands.is_empty() {
+            return Err("at least one height band is needed".to_string());
+        }
+        for (index, band) in self.height_bands.iter().enumerate() {
+            if band.min_height.is_nan() {
+                return Err(format!("height band {} has no valid min_height", index));
+            }
+            match band.tile.definition() {
+                None => {
+                    return Err(format!(
+                        "height band {} uses unknown tile {}",
+                        index, band.tile.0
+                    ))
+                }
+                Some(definition) if definition.layer != TileLayer::Terrain => {
+                    return Err(format!(
+                        "height band {} uses {}, which isn't a terrain tile",
+                        index, definition.name
+                    ))
+                }
+                Some(_) => (),
+            }
+            let Some(previous) = index.checked_sub(1).map(|index| &self.height_bands[index]) else {
+                continue;
+            };
+            if band.min_height == previous.min_height {
+                return Err(format!(
+                    "height bands {} ({}) and {} ({}) overlap, both start at {}",
+                    index - 1,
+                    previous.tile.name(),
+                    index,
+                    band.tile.name(),
+                    band.min_height
+                ));
+            }
+            if band.min_height < previous.min_height {
+                return Err(format!(
+                    "height bands are not sorted, band {} ({}) starts at {} which is below band {} ({}) at {}",
+                    index,
+                    band.tile.name(),
+                    band.min_height,
+                    index - 1,
+                    previous.tile.name(),
+                    previous.min_height
+                ));
+            }
+        }
+        for (index, rule) in self.decorations.iter().enumerate() {
+            match rule.tile.definition() {
+                None => {
+                    return Err(format!(
+                        "decoration {} uses unknown tile {}",
+                        index, rule.tile.0
+                    ))
+                }
+                Some(definition) if definition.layer == TileLayer::Terrain => {
+                    return Err(format!(
+                        "decoration {} uses {}, which is a terrain tile",
+                        index, definition.name
+                    ))
+                }
+                Some(_) => (),
+            }
+            if !tile_registry().contains(rule.on) {
+                return Err(format!(
+                    "decoration {} is placed on unknown tile {}",
+                    index, rule.on.0
+                ));
+            }
+            if !(0.0..=1.0).contains(&rule.density) {
+                return Err(format!(
+                    "decoration {} has density {}, expected 0 to 1",
+                    index, rule.density
+                ));
+            }
+        }
+        if !(1..=Fbm::<Perlin>::MAX_OCTAVES).contains(&self.octaves) {
+            return Err(format!(
+                "octaves must be between 1 and {}, got {}",
+                Fbm::<Perlin>::MAX_OCTAVES,
+                self.octaves
+            ));
+        }
+        for (name, value) in [
+            ("height_scale_factor", self.height_scale_factor),
+            ("frequency", self.frequency),
+            ("lacunarity", self.lacunarity),
+            ("persistence", self.persistence),
+        ] {
+            if !value.is_finite() {
+                return Err(format!("{} must be a finite number, got {}", name, value));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct GlobalTilePos(pub i32, pub i32);
 
+pub const CHUNK_SIZE: i32 = 16; // Width and height of a chunk in tiles
+pub const TILES_PER_CHUNK: usize = (CHUNK_SIZE * CHUNK_SIZE) as usize;
+
+impl GlobalTilePos {
+    // Returns the chunk containing the tile and the tile's index in that chunk's tiles. Uses
+    // euclidean division, so tile -1 is the last tile of chunk -1 rather than wrapping around.
+    pub fn to_chunk_local(&self) -> (ChunkPos, usize) {
+        let chunk_pos = ChunkPos {
+            x: self.0.div_euclid(CHUNK_SIZE),
+            y: self.1.div_euclid(CHUNK_SIZE),
+        };
+        let local_x = self.0.rem_euclid(CHUNK_SIZE);
+        let local_y = self.1.rem_euclid(CHUNK_SIZE);
+        (chunk_pos, (local_x + local_y * CHUNK_SIZE) as usize)
+    }
+
+    pub fn chunk_pos(&self) -> ChunkPos {
+        self.to_chunk_local().0
+    }
+}
+
+impl ChunkPos {
+    // Global position of the tile at a local index in this chunk, the inverse of to_chunk_local
+    pub fn tile_pos(&self, local_index: usize) -> GlobalTilePos {
+        let local_index = local_index as i32;
+        GlobalTilePos(
+            self.x * CHUNK_SIZE + local_index % CHUNK_SIZE,
+            self.y * CHUNK_SIZE + local_index / CHUNK_SIZE,
+        )
+    }
+}
+
+// Area of the world that is in view, in tiles. Like GlobalTilePos, x grows to the right and y grows
+// downwards, so a view of just the tile at the origin is left 0, top 0, right 1, bottom 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewRect {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl ViewRect {
+    // Returns the top left and bottom right tiles overlapping the view, inclusive. A view that is
+    // empty or inverted gives a bottom right tile above or left of the top left one.
+    pub fn tile_bounds(&self) -> (GlobalTilePos, GlobalTilePos) {
+        (
+            GlobalTilePos(self.left.floor() as i32, self.top.floor() as i32),
+            GlobalTilePos(self.right.ceil() as i32 - 1, self.bottom.ceil() as i32 - 1),
+        )
+    }
+
+    // Returns the top left and bottom right chunks overlapping the view, inclusive
+    pub fn chunk_bounds(&self) -> (ChunkPos, ChunkPos) {
+        let (top_left, bottom_right) = self.tile_bounds();
+        let (min_chunk, mut max_chunk) = (top_left.chunk_pos(), bottom_right.chunk_pos());
+        // An empty view can have both corners in the same chunk, keep its chunk range empty too
+        if bottom_right.0 < top_left.0 {
+            max_chunk.x = min_chunk.x - 1;
+        }
+        if bottom_right.1 < top_left.1 {
+            max_chunk.y = min_chunk.y - 1;
+        }
+        (min_chunk, max_chunk)
+    }
+
+    pub fn contains_chunk(&self, chunk_pos: &ChunkPos) -> bool {
+        let (top_left, bottom_right) = self.chunk_bounds();
+        (top_left.x..=bottom_right.x).contains(&chunk_pos.x)
+            && (top_left.y..=bottom_right.y).contains(&chunk_pos.y)
+    }
+}
+
+impl WorldGenerationType {
+    pub fn id(&self) -> u8 {
+        match self {
+            WorldGenerationType::WaterWorld => 0,
+            WorldGenerationType::ChunkMess => 1,
+            WorldGenerationType::TileMess => 2,
+            WorldGenerationType::PerlinTerrain => 3,
+            WorldGenerationType::InfinitePerlinTerrain => 4,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<WorldGenerationType> {
+        match id {
+            0 => Some(WorldGenerationType::WaterWorld),
+            1 => Some(WorldGenerationType::ChunkMess),
+            2 => Some(WorldGenerationType::TileMess),
+            3 => Some(WorldGenerationType::PerlinTerrain),
+            4 => Some(WorldGenerationType::InfinitePerlinTerrain),
+            _ => None,
+        }
+    }
+}
+
+impl WorldIslandSize {
+    pub fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_id(id: u8) -> Option<WorldIslandSize> {
+        match id {
+            2 => Some(WorldIslandSize::Tiny),
+            4 => Some(WorldIslandSize::Small),
+            5 => Some(WorldIslandSize::Medium),
+            8 => Some(WorldIslandSize::Large),
+            11 => Some(WorldIslandSize::Huge),
+            15 => Some(WorldIslandSize::Titanic),
+            _ => None,
+        }
+    }
+}
+
 // Standered functions for creating and modifying world
 impl World {
     // Returns an empty world
     pub fn new() -> Self {
         return World {
             chunks: HashMap::new(),
+            seed: 0,
+            generation_type: None,
+            island_size: None,
+            perlin_settings: PerlinWorldSettings::default(),
+            chunk_generator: None,
+            chunk_pipeline: None,
+            region_storage: None,
+            dirty_chunks: HashSet::new(),
         };
     }
 
     pub fn contains_tile(&self, global_pos: &GlobalTilePos) -> bool {
-        match self.get_tile(global_pos) {
+        match self.loaded_tile(global_pos) {
             Some(_) => true,
             None => false,
         }
     }
 
+    // Sets the settings used by perlin world generation, call before generating the world
+    pub fn with_perlin_settings(mut self, settings: PerlinWorldSettings) -> Self {
+        self.perlin_settings = settings;
+        self
+    }
+
     // Populates a world with tiles, with diffrent world types able to be generated
     pub fn generate_world(
-        self,
+        mut self,
         generation_type: WorldGenerationType,
         size: WorldGenerationSize,
         island_size: WorldIslandSize,
@@ -102,125 +710,92 @@ impl World {
             WorldGenerationType::ChunkMess => Self::generate_chunk_mess_world(size, seed),
             WorldGenerationType::TileMess => Self::generate_tile_mess_world(size, seed),
             WorldGenerationType::PerlinTerrain => {
-                Self::generate_perlin_noise_world(size, island_size, seed)
+                Self::generate_perlin_noise_world(size, island_size, seed, &self.perlin_settings)
             }
+            // Chunks are generated as they become visible
+            WorldGenerationType::InfinitePerlinTerrain => HashMap::new(),
         };
 
-        return World { chunks };
+        self.chunks = chunks;
+        self.seed = seed;
+        self.generation_type = Some(generation_type);
+        self.island_size = Some(island_size);
+        self.chunk_generator =
+            Self::make_chunk_generator(generation_type, island_size, seed, &self.perlin_settings)
+                .map(Arc::new);
+        self.chunk_pipeline = None;
+        return self;
     }
 
-    // Gets immutable referance to tile from global tile position
-    pub fn get_tile(&self, pos: &GlobalTilePos) -> Option<&Tile> {
-        let chunk_x = if pos.0 < 0 {
-            (pos.0 - 15) / 16
-        } else {
-            pos.0 / 16
-        };
-        let chunk_y = if pos.1 < 0 {
-            (pos.1 - 15) / 16
-        } else {
-            pos.1 / 16
-        };
-
-        let tile_x = pos.0 % 16;
-        let tile_y = pos.1 % 16;
-        if let Some(chunk) = self.chunks.get(&ChunkPos {
-            x: chunk_x,
-            y: chunk_y,
-        }) {
-            if let Some(tile) = chunk.tiles.get({ tile_x + tile_y * 16 } as usize) {
-                return Some(tile);
-            }
-        }
-        None
+    // Gets referance to tile from global tile position, loading its chunk from disk if needed
+    pub fn get_tile(&mut self, pos: &GlobalTilePos) -> Option<&TileId> {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        self.load_chunk(&chunk_pos);
+        Some(self.chunks.get(&chunk_pos)?.tiles.get(local_index))
     }
 
-    // Gets mutable referance to tile from global tile position
-    pub fn get_tile_mut(&mut self, pos: &GlobalTilePos) -> Option<&mut Tile> {
-        let chunk_x = if pos.0 < 0 {
-            (pos.0 - 15) / 16
-        } else {
-            pos.0 / 16
-        };
-        let chunk_y = if pos.1 < 0 {
-            (pos.1 - 15) / 16
-        } else {
-            pos.1 / 16
-        };
+    // Gets referance to tile from global tile position, None if its chunk isn't in memory
+    pub fn loaded_tile(&self, pos: &GlobalTilePos) -> Option<&TileId> {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        Some(self.chunks.get(&chunk_pos)?.tiles.get(local_index))
+    }
 
-        let tile_x = pos.0 % 16;
-        let tile_y = pos.1 % 16;
-        if let Some(chunk) = self.chunks.get_mut(&ChunkPos {
-            x: chunk_x,
-            y: chunk_y,
-        }) {
-            // If tile exists in world
-            if let Some(tile) = chunk.tiles.get_mut({ tile_x + tile_y * 16 } as usize) {
-                return Some(tile);
-            }
-        }
-        None
+    // Gets the tile of a layer, None if its chunk isn't loaded or an overlay layer is empty there
+    pub fn get_layer_tile(&self, pos: &GlobalTilePos, layer: TileLayer) -> Option<TileId> {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        self.chunks.get(&chunk_pos)?.layer_tile(layer, local_index)
     }
 
-    // Gets mutable referance to tile from mouse position
-    pub fn get_tile_mut_mouse(&mut self, camera: &Camera2D) -> Option<&mut Tile> {
-        let grid_pos = camera.screen_to_world(mouse_position().into()) / TILE_SIZE;
-        let tile = self.get_tile_mut(&GlobalTilePos(grid_pos.x as i32, -grid_pos.y as i32));
-        match tile {
-            Some(tile) => return Some(tile),
-            None => (),
+    // Sets or, with None, clears the tile of a layer, loading its chunk from disk if needed. The
+    // terrain can't be cleared. Replacing a tile with a different one drops its metadata. Returns
+    // false if the tile couldn't be set.
+    pub fn set_layer_tile(
+        &mut self,
+        pos: &GlobalTilePos,
+        layer: TileLayer,
+        tile: Option<TileId>,
+    ) -> bool {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        self.load_chunk(&chunk_pos);
+        let Some(chunk) = self.chunks.get_mut(&chunk_pos) else {
+            return false;
         };
-        None
-    }
-
-    fn get_visible_tiles(&self, camera: &Camera2D) -> Vec<GlobalTilePos> {
-        let top_left_world_pos = camera.screen_to_world(Vec2 { x: 0.0, y: 0.0 });
-        let bottom_right_world_pos = camera.screen_to_world(Vec2 {
-            x: screen_width(),
-            y: screen_height(),
-        });
-        let top_left_grid_pos = top_left_world_pos / 8.0;
-        let bottom_right_grid_pos = bottom_right_world_pos / 8.0;
-        let mut visible_tiles: Vec<GlobalTilePos> = Vec::new();
-        for y in -top_left_grid_pos.y as i32..=-bottom_right_grid_pos.y as i32 + 2 {
-            for x in top_left_grid_pos.x as i32..=bottom_right_grid_pos.x as i32 + 2 {
-                visible_tiles.push(GlobalTilePos(x, y));
-            }
+        let previous = chunk.layer_tile(layer, local_index);
+        match (chunk.overlay_mut(layer), tile) {
+            (Some(overlay), tile) => overlay.set(local_index, tile),
+            (None, Some(tile)) => chunk.tiles.set(local_index, tile),
+            (None, None) => return false,
         }
-        visible_tiles
+        if previous != tile {
+            chunk.metadata.remove(layer, local_index);
+        }
+        chunk.mark_changed();
+        self.dirty_chunks.insert(chunk_pos);
+        true
     }
-}
 
-// Rendering for tiles
-impl World {
-    // Renders tiles that are visble to the camera
-    pub fn render_visible_tiles(&self, camera: &Camera2D, asset_handle: &AssetHandle) {
-        let visible_tiles = self.get_visible_tiles(&camera);
-        for global_pos in &visible_tiles {
-            if self.contains_tile(global_pos) {
-                self.render_tile(&asset_handle, global_pos);
-            }
-        }
+    // Gets the uppermost tile of any layer, None if its chunk isn't loaded
+    pub fn get_top_tile(&self, pos: &GlobalTilePos) -> Option<TileId> {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        Some(self.chunks.get(&chunk_pos)?.top_tile(local_index))
     }
+}
 
-    // Renders a tile given a global_position
-    fn render_tile(&self, asset_handle: &AssetHandle, global_pos: &GlobalTilePos) {
-        let (x, y) = (global_pos.0, global_pos.1);
-        // If tile exists, draw it
-        if let Some(tile) = self.get_tile(global_pos) {
-            draw_texture_ex(
-                asset_handle.tile_atlas.0,
-                { x as f32 * TILE_SIZE }.round(),
-                { -y as f32 * TILE_SIZE }.round() - TILE_SIZE,
-                WHITE,
-                DrawTextureParams {
-                    dest_size: Some(Vec2::new(TILE_SIZE, TILE_SIZE)),
-                    source: Some(get_atlas_rect(tile)),
-                    flip_y: true,
-                    ..Default::default()
-                },
-            );
-        } else {
-        } // Else, dont draw anything
+// Visibility
+impl World {
+    // Iterates over the chunks in memory that overlap the view, row by row
+    pub fn visible_chunks<'a>(
+        &'a self,
+        view: &ViewRect,
+    ) -> impl Iterator<Item = (ChunkPos, &'a Chunk)> + 'a {
+        let (top_left, bottom_right) = view.chunk_bounds();
+        (top_left.y..=bottom_right.y)
+            .flat_map(move |chunk_y| {
+                (top_left.x..=bottom_right.x).map(move |chunk_x| ChunkPos {
+                    x: chunk_x,
+                    y: chunk_y,
+                })
+            })
+            .filter_map(|chunk_pos| Some((chunk_pos, self.chunks.get(&chunk_pos)?)))
     }
 }
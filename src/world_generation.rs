@@ -1,5 +1,4 @@
-use noise::utils::{NoiseMapBuilder, PlaneMapBuilder};
-use noise::{Fbm, Perlin};
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
 use std::collections::HashMap;
@@ -9,26 +8,58 @@ use crate::world::*;
 
 // Wolrd generation methods
 impl World {
+    // Returns the generator for world types that generate chunks on demand
+    pub fn make_chunk_generator(
+        generation_type: WorldGenerationType,
+        island_size: WorldIslandSize,
+        seed: u32,
+        settings: &PerlinWorldSettings,
+    ) -> Option<PerlinChunkGenerator> {
+        match generation_type {
+            WorldGenerationType::InfinitePerlinTerrain => Some(PerlinChunkGenerator::new(
+                seed,
+                island_size,
+                settings.clone(),
+            )),
+            _ => None,
+        }
+    }
+
+    // Generates a chunk if the world has a chunk generator and the chunk doesn't exist yet.
+    // With a generation pipeline the chunk is only requested. Returns if the chunk exists.
+    pub fn generate_chunk(&mut self, chunk_pos: &ChunkPos) -> bool {
+        if self.chunks.contains_key(chunk_pos) {
+            return true;
+        }
+        let Some(generator) = &self.chunk_generator else {
+            return false;
+        };
+        if !generator.contains_chunk(chunk_pos) {
+            return false;
+        }
+        if let Some(pipeline) = &mut self.chunk_pipeline {
+            pipeline.request_chunk(chunk_pos);
+            return false;
+        }
+        self.chunks
+            .insert(*chunk_pos, generator.generate_chunk(chunk_pos));
+        self.dirty_chunks.insert(*chunk_pos);
+        true
+    }
+
     // Generates world of just water tiles
     pub fn generate_water_world(size: WorldGenerationSize, _seed: u32) -> HashMap<ChunkPos, Chunk> {
         let world_size = size as i32;
         let mut chunks = HashMap::new();
         for chunk_y in 0..world_size {
             for chunk_x in 0..world_size {
-                // Generating individual chunks
-                let mut current_chunk = Vec::new();
-                for _ in 0..16 * 16 {
-                    current_chunk.push(Tile::Water);
-                }
                 // Insert chunk into chunks hashmap
                 chunks.insert(
                     ChunkPos {
                         x: chunk_x,
                         y: chunk_y,
                     },
-                    Chunk {
-                        tiles: current_chunk,
-                    },
+                    Chunk::filled(TileId::WATER),
                 );
             }
         }
@@ -52,19 +83,13 @@ impl World {
                 // Randomize tile used for chunk
                 let current_chunk_tile = random_tile(&mut rng);
 
-                let mut current_chunk = Vec::new();
-                for _ in 0..16 * 16 {
-                    current_chunk.push(current_chunk_tile.clone());
-                }
                 // Insert chunk into chunks hashmap
                 chunks.insert(
                     ChunkPos {
                         x: chunk_x,
                         y: chunk_y,
                     },
-                    Chunk {
-                        tiles: current_chunk,
-                    },
+                    Chunk::filled(current_chunk_tile),
                 );
             }
         }
@@ -85,20 +110,15 @@ impl World {
         for chunk_y in 0..world_size {
             for chunk_x in 0..world_size {
                 // Generating individual chunks
-                let mut current_chunk = Vec::new();
-                for _ in 0..16 * 16 {
-                    let current_chunk_tile = random_tile(&mut rng);
-                    current_chunk.push(current_chunk_tile.clone());
-                }
+                let current_chunk: [TileId; TILES_PER_CHUNK] =
+                    std::array::from_fn(|_| random_tile(&mut rng));
                 // Insert chunk into chunks hashmap
                 chunks.insert(
                     ChunkPos {
                         x: chunk_x,
                         y: chunk_y,
                     },
-                    Chunk {
-                        tiles: current_chunk,
-                    },
+                    Chunk::new(current_chunk),
                 );
             }
         }
@@ -109,71 +129,147 @@ impl World {
         size: WorldGenerationSize,
         island_size: WorldIslandSize,
         seed: u32,
+        settings: &PerlinWorldSettings,
     ) -> HashMap<ChunkPos, Chunk> {
-        let fbm = Fbm::<Perlin>::new(seed);
         let size = size as i32;
+        let generator =
+            PerlinChunkGenerator::for_finite_world(seed, size, island_size, settings.clone());
         let mut map = HashMap::new();
-
-        let plane = PlaneMapBuilder::<_, 2>::new(&fbm)
-            .set_size(size as usize * 16, size as usize * 16)
-            .set_x_bounds(
-                -size as f64 / island_size as i64 as f64,
-                size as f64 / island_size as i64 as f64,
-            )
-            .set_y_bounds(
-                -size as f64 / island_size as i64 as f64,
-                size as f64 / island_size as i64 as f64,
-            )
-            .build();
-
-        let terrain_height_offset = 0.4;
-
-        let sand_level = 0.29 + terrain_height_offset;
-        let grass_level = 0.8 + terrain_height_offset;
-        let stone_level = 1.1 + terrain_height_offset; // Change back to 1.0
-        let dark_stone_level = 1.5 + terrain_height_offset;
-        let snow_level = 2.0 + terrain_height_offset;
-
-        let shallow_water_level = 0.0 + terrain_height_offset;
-        let water_level = -0.50 + shallow_water_level;
-        let deep_water_level = -1.55 + terrain_height_offset;
-
-        let max_height = snow_level;
-        let height_scale_factor = 1.9;
-
         for chunk_y in 0..size {
             for chunk_x in 0..size {
-                let mut chunk = Chunk { tiles: vec![] };
-                for y in 0..16 {
-                    for x in 0..16 {
-                        let pixel = plane
-                            .get_value({ chunk_x * 16 + x } as usize, { chunk_y * 16 + y }
-                                as usize);
-                        let pixel = { height_scale_factor * pixel };
-                        let tile = match pixel {
-                            // Land
-                            _ if pixel > max_height => Tile::Snow, // Handles for anything above the max height, makes it dark stone
-                            _ if pixel >= dark_stone_level && pixel < max_height => Tile::Snow,
-                            _ if pixel >= stone_level && pixel < dark_stone_level => {
-                                Tile::DarkStone
-                            }
-                            _ if pixel >= grass_level && pixel < stone_level => Tile::Stone,
-                            _ if pixel >= sand_level && pixel < grass_level => Tile::Grass,
-                            _ if pixel >= shallow_water_level && pixel < sand_level => Tile::Sand,
-
-                            // Water
-                            _ if pixel < shallow_water_level && pixel >= water_level => {
-                                Tile::ShallowWater
-                            }
-                            _ if pixel < water_level && pixel >= deep_water_level => Tile::Water,
-                            _ => Tile::DeepWater,
-                        };
-                        chunk.tiles.push(tile);
-                    }
-                }
-                map.insert(ChunkPos { x: chunk_x, y: chunk_y}, chunk);
+                let chunk_pos = ChunkPos {
+                    x: chunk_x,
+                    y: chunk_y,
+                };
+                map.insert(chunk_pos, generator.generate_chunk(&chunk_pos));
             }
         }
         map
     }
 }
+
+// Generates perlin terrain one chunk at a time. Every tile is sampled from the noise at its
+// global position, so chunks can be generated in any order and still line up seamlessly.
+pub struct PerlinChunkGenerator {
+    fbm: Fbm<Perlin>,
+    seed: u32,
+    settings: PerlinWorldSettings,
+    origin: (f64, f64), // Noise coordinates of tile (0, 0)
+    step: (f64, f64),   // Noise units between neighbouring tiles
+    size: Option<i32>,  // Width and height of finite worlds in chunks
+}
+
+impl PerlinChunkGenerator {
+    // Generator for infinite worlds, centered on the origin
+    pub fn new(seed: u32, island_size: WorldIslandSize, settings: PerlinWorldSettings) -> Self {
+        let step = 2.0 / island_size as i64 as f64 / 16.0;
+        PerlinChunkGenerator {
+            fbm: Self::make_fbm(seed, &settings),
+            seed,
+            settings,
+            origin: (0.0, 0.0),
+            step: (step, step),
+            size: None,
+        }
+    }
+
+    // Generator for a size x size chunk world, sampling the same points a PlaneMapBuilder
+    // spanning the whole world would
+    pub fn for_finite_world(
+        seed: u32,
+        size: i32,
+        island_size: WorldIslandSize,
+        settings: PerlinWorldSettings,
+    ) -> Self {
+        let bounds = (
+            -size as f64 / island_size as i64 as f64,
+            size as f64 / island_size as i64 as f64,
+        );
+        let step = (bounds.1 - bounds.0) / (size as usize * 16) as f64;
+        PerlinChunkGenerator {
+            fbm: Self::make_fbm(seed, &settings),
+            seed,
+            settings,
+            origin: (bounds.0, bounds.0),
+            step: (step, step),
+            size: Some(size),
+        }
+    }
+
+    fn make_fbm(seed: u32, settings: &PerlinWorldSettings) -> Fbm<Perlin> {
+        Fbm::<Perlin>::new(seed)
+            .set_octaves(settings.octaves)
+            .set_frequency(settings.frequency)
+            .set_lacunarity(settings.lacunarity)
+            .set_persistence(settings.persistence)
+    }
+
+    // Returns if the chunk is part of the world, always true for infinite worlds
+    pub fn contains_chunk(&self, chunk_pos: &ChunkPos) -> bool {
+        match self.size {
+            Some(size) => (0..size).contains(&chunk_pos.x) && (0..size).contains(&chunk_pos.y),
+            None => true,
+        }
+    }
+
+    pub fn generate_chunk(&self, chunk_pos: &ChunkPos) -> Chunk {
+        let mut tiles = [TileId::GRASS; TILES_PER_CHUNK];
+        for y in 0..16 {
+            for x in 0..16 {
+                let tile_x = chunk_pos.x as i64 * 16 + x;
+                let tile_y = chunk_pos.y as i64 * 16 + y;
+                let pixel = self.fbm.get([
+                    self.origin.0 + self.step.0 * tile_x as f64,
+                    self.origin.1 + self.step.1 * tile_y as f64,
+                ]);
+                tiles[(x + y * 16) as usize] = self.height_to_tile(pixel);
+            }
+        }
+        let mut chunk = Chunk::new(tiles);
+        self.scatter_decorations(chunk_pos, &mut chunk);
+        chunk
+    }
+
+    // Places the decoration rules' tiles. Whether a tile gets a decoration only depends on its
+    // position, so it doesn't matter in which order chunks are generated.
+    fn scatter_decorations(&self, chunk_pos: &ChunkPos, chunk: &mut Chunk) {
+        for (rule_index, rule) in self.settings.decorations.iter().enumerate() {
+            let layer = rule
+                .tile
+                .definition()
+                .map_or(TileLayer::Decoration, |definition| definition.layer);
+            for local_index in 0..TILES_PER_CHUNK {
+                // Terrain layer tiles are never empty, so they are never placed here
+                if *chunk.tiles.get(local_index) != rule.on
+                    || chunk.layer_tile(layer, local_index).is_some()
+                {
+                    continue;
+                }
+                let pos = chunk_pos.tile_pos(local_index);
+                if tile_chance(self.seed, &pos, rule_index as u32) >= rule.density {
+                    continue;
+                }
+                if let Some(overlay) = chunk.overlay_mut(layer) {
+                    overlay.set(local_index, Some(rule.tile));
+                }
+            }
+        }
+    }
+
+    fn height_to_tile(&self, pixel: f64) -> TileId {
+        let pixel = { self.settings.height_scale_factor * pixel };
+        self.settings.tile_at_height(pixel)
+    }
+}
+
+// Number from 0 to 1 that looks random but is always the same for a seed, tile and salt
+fn tile_chance(seed: u32, pos: &GlobalTilePos, salt: u32) -> f64 {
+    let mut hash = seed ^ salt.wrapping_mul(0x27D4_EB2F);
+    for value in [pos.0 as u32, pos.1 as u32] {
+        hash = (hash ^ value).wrapping_mul(0x9E37_79B1);
+        hash ^= hash >> 15;
+    }
+    hash = hash.wrapping_mul(0x85EB_CA77);
+    hash ^= hash >> 13;
+    hash as f64 / u32::MAX as f64
+}
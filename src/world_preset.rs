@@ -0,0 +1,110 @@
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::world::*;
+
+// A named description of a world that can be shipped as a TOML file, for example:
+//
+//   name = "Archipelago"
+//   generation_type = "PerlinTerrain"
+//   size = "Large"
+//   island_size = "Small"
+//   seed = 25
+//
+//   [perlin]
+//   height_scale_factor = 1.9
+//   height_bands = [
+//       { min_height = -inf, tile = "DeepWater" },
+//       { min_height = 0.4, tile = "Sand" },
+//   ]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorldPreset {
+    pub name: String,
+    pub generation_type: WorldGenerationType,
+    pub size: WorldGenerationSize,
+    pub island_size: WorldIslandSize,
+    pub seed: u32,
+    #[serde(default)]
+    pub perlin: PerlinWorldSettings, // Missing settings fall back to the defaults
+}
+
+#[derive(Debug)]
+pub enum PresetError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PresetError::Io(err) => write!(f, "could not read preset: {}", err),
+            PresetError::Parse(err) => write!(f, "could not parse preset: {}", err),
+            PresetError::Invalid(message) => write!(f, "invalid preset: {}", message),
+        }
+    }
+}
+
+impl Default for WorldPreset {
+    fn default() -> Self {
+        WorldPreset {
+            name: "Default".to_string(),
+            generation_type: WorldGenerationType::PerlinTerrain,
+            size: WorldGenerationSize::Large,
+            island_size: WorldIslandSize::Large,
+            seed: 25,
+            perlin: PerlinWorldSettings::default(),
+        }
+    }
+}
+
+impl WorldPreset {
+    pub fn load(path: impl AsRef<Path>) -> Result<WorldPreset, PresetError> {
+        let text = fs::read_to_string(path).map_err(PresetError::Io)?;
+        Self::from_toml_str(&text)
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<WorldPreset, PresetError> {
+        let preset: WorldPreset = toml::from_str(text).map_err(PresetError::Parse)?;
+        preset.perlin.validate().map_err(PresetError::Invalid)?;
+        Ok(preset)
+    }
+
+    // Generates the world described by the preset, returning once every chunk is generated.
+    // Infinite worlds start out empty and generate chunks as they are needed.
+    pub fn generate_blocking(&self) -> World {
+        World::new()
+            .with_perlin_settings(self.perlin.clone())
+            .generate_world(self.generation_type, self.size, self.island_size, self.seed)
+    }
+
+    // Name for files of the world, like region files, that differs between presets and seeds.
+    // Characters that aren't safe in file names are replaced.
+    pub fn storage_name(&self) -> String {
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        format!(
+            "{}-{:?}-{:?}-{:?}-{}",
+            name, self.generation_type, self.size, self.island_size, self.seed
+        )
+    }
+
+    // Starts generating the world described by the preset on worker threads
+    pub fn generate(&self) -> World {
+        World::new()
+            .with_perlin_settings(self.perlin.clone())
+            .generate_world_in_background(
+                self.generation_type,
+                self.size,
+                self.island_size,
+                self.seed,
+            )
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::chunk_palette::PalettedTiles;
+use crate::tile_metadata::{ChunkMetadata, MetadataValue, TileMetadata};
+use crate::tile_registry::tile_registry;
+use crate::world::*;
+
+// World file layout (all numbers little endian):
+//   magic "WRLD", format version (u16), generation type (u8, 255 = none), seed (u32),
+//   island size (u8, 255 = none), perlin settings (see write_perlin_settings),
+//   chunk count (u32), then for every chunk: x (i32), y (i32) and its tiles (see write_chunk)
+const WORLD_FILE_MAGIC: &[u8; 4] = b"WRLD";
+pub const WORLD_FILE_VERSION: u16 = 1;
+const NO_GENERATION_TYPE: u8 = u8::MAX;
+const NO_ISLAND_SIZE: u8 = u8::MAX;
+
+// Saving and loading worlds to and from disk
+impl World {
+    // Saves the world to a file, overwriting it if it already exists
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    // Loads a world previously written with World::save
+    pub fn load(path: impl AsRef<Path>) -> io::Result<World> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::read_from(&mut reader)
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(WORLD_FILE_MAGIC)?;
+        writer.write_all(&WORLD_FILE_VERSION.to_le_bytes())?;
+        let generation_type = match self.generation_type {
+            Some(generation_type) => generation_type.id(),
+            None => NO_GENERATION_TYPE,
+        };
+        writer.write_all(&[generation_type])?;
+        writer.write_all(&self.seed.to_le_bytes())?;
+        let island_size = match self.island_size {
+            Some(island_size) => island_size.id(),
+            None => NO_ISLAND_SIZE,
+        };
+        writer.write_all(&[island_size])?;
+        write_perlin_settings(writer, &self.perlin_settings)?;
+
+        // Chunks are written in a fixed order so the same world always produces the same file
+        let mut chunks: Vec<(&ChunkPos, &Chunk)> = self.chunks.iter().collect();
+        chunks.sort_by_key(|(chunk_pos, _)| (chunk_pos.y, chunk_pos.x));
+
+        writer.write_all(&(chunks.len() as u32).to_le_bytes())?;
+        for (chunk_pos, chunk) in chunks {
+            writer.write_all(&chunk_pos.x.to_le_bytes())?;
+            writer.write_all(&chunk_pos.y.to_le_bytes())?;
+            write_chunk(writer, chunk)?;
+        }
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> io::Result<World> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != WORLD_FILE_MAGIC {
+            return Err(invalid_data("not a world file"));
+        }
+        let version = u16::from_le_bytes(read_array(reader)?);
+        if version != WORLD_FILE_VERSION {
+            return Err(invalid_data(&format!(
+                "unsupported world file version {}",
+                version
+            )));
+        }
+
+        let [generation_type] = read_array(reader)?;
+        let generation_type = match generation_type {
+            NO_GENERATION_TYPE => None,
+            id => Some(
+                WorldGenerationType::from_id(id)
+                    .ok_or_else(|| invalid_data(&format!("unknown generation type {}", id)))?,
+            ),
+        };
+        let seed = u32::from_le_bytes(read_array(reader)?);
+        let [island_size] = read_array(reader)?;
+        let island_size = match island_size {
+            NO_ISLAND_SIZE => None,
+            id => Some(
+                WorldIslandSize::from_id(id)
+                    .ok_or_else(|| invalid_data(&format!("unknown island size {}", id)))?,
+            ),
+        };
+
+        let perlin_settings = read_perlin_settings(reader)?;
+
+        let chunk_count = u32::from_le_bytes(read_array(reader)?);
+        let mut chunks = HashMap::new();
+        for _ in 0..chunk_count {
+            let x = i32::from_le_bytes(read_array(reader)?);
+            let y = i32::from_le_bytes(read_array(reader)?);
+            chunks.insert(ChunkPos { x, y }, read_chunk(reader)?);
+        }
+
+        // Infinite worlds keep generating the chunks that were never saved
+        let chunk_generator = match (generation_type, island_size) {
+            (Some(generation_type), Some(island_size)) => {
+                Self::make_chunk_generator(generation_type, island_size, seed, &perlin_settings)
+                    .map(Arc::new)
+            }
+            _ => None,
+        };
+
+        Ok(World {
+            chunks,
+            seed,
+            generation_type,
+            island_size,
+            perlin_settings,
+            chunk_generator,
+            ..World::new()
+        })
+    }
+}
+
+// Writes the tiles of a single chunk: the terrain palette encoded, then for the decoration and
+// object layers a flag (u8) saying whether the layer has any tiles, followed by its tiles palette
+// encoded if it does, with empty spots as TileId::RESERVED. Then the chunk's tile metadata, see
+// write_chunk_metadata.
+pub fn write_chunk<W: Write>(writer: &mut W, chunk: &Chunk) -> io::Result<()> {
+    PalettedTiles::encode(&chunk.tiles.to_array()).write_to(writer)?;
+    for overlay in TileLayer::OVERLAYS
+        .map(|layer| chunk.overlay(layer))
+        .iter()
+        .flatten()
+    {
+        if overlay.is_empty() {
+            writer.write_all(&[0])?;
+            continue;
+        }
+        writer.write_all(&[1])?;
+        let tiles =
+            std::array::from_fn(|local_index| overlay.get(local_index).unwrap_or(TileId::RESERVED));
+        PalettedTiles::encode(&tiles).write_to(writer)?;
+    }
+    write_chunk_metadata(writer, &chunk.metadata)
+}
+
+// Reads the tiles of a single chunk written with write_chunk
+pub fn read_chunk<R: Read>(reader: &mut R) -> io::Result<Chunk> {
+    let mut chunk = Chunk::new(read_paletted_tiles(reader, false)?);
+    for layer in TileLayer::OVERLAYS {
+        let [has_tiles] = read_array(reader)?;
+        if has_tiles == 0 {
+            continue;
+        }
+        let tiles = read_paletted_tiles(reader, true)?;
+        if let Some(overlay) = chunk.overlay_mut(layer) {
+            for (local_index, tile) in tiles.into_iter().enumerate() {
+                overlay.set(
+                    local_index,
+                    Some(tile).filter(|tile| *tile != TileId::RESERVED),
+                );
+            }
+        }
+    }
+    chunk.metadata = read_chunk_metadata(reader)?;
+    Ok(chunk)
+}
+
+// Tile metadata layout: count of tiles with metadata (u32), then for every tile its layer (u8),
+// local index (u8) and value count (u16), followed by every value as key length (u16) and key
+// (utf8), then a tag (u8) and either an integer (i64, tag 0) or text length (u32) and text (utf8,
+// tag 1)
+const METADATA_INTEGER: u8 = 0;
+const METADATA_TEXT: u8 = 1;
+
+fn write_chunk_metadata<W: Write>(writer: &mut W, metadata: &ChunkMetadata) -> io::Result<()> {
+    writer.write_all(&(metadata.iter().count() as u32).to_le_bytes())?;
+    for (layer, local_index, tile_metadata) in metadata.iter() {
+        writer.write_all(&[layer.id(), local_index as u8])?;
+        writer.write_all(&(tile_metadata.values.len() as u16).to_le_bytes())?;
+        for (key, value) in &tile_metadata.values {
+            let key_length = u16::try_from(key.len()).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidInput, "metadata key is too long")
+            })?;
+            writer.write_all(&key_length.to_le_bytes())?;
+            writer.write_all(key.as_bytes())?;
+            match value {
+                MetadataValue::Integer(value) => {
+                    writer.write_all(&[METADATA_INTEGER])?;
+                    writer.write_all(&value.to_le_bytes())?;
+                }
+                MetadataValue::Text(text) => {
+                    writer.write_all(&[METADATA_TEXT])?;
+                    writer.write_all(&(text.len() as u32).to_le_bytes())?;
+                    writer.write_all(text.as_bytes())?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_chunk_metadata<R: Read>(reader: &mut R) -> io::Result<ChunkMetadata> {
+    let mut metadata = ChunkMetadata::default();
+    let tile_count = u32::from_le_bytes(read_array(reader)?);
+    for _ in 0..tile_count {
+        let [layer, local_index] = read_array(reader)?;
+        let layer = TileLayer::from_id(layer)
+            .ok_or_else(|| invalid_data(&format!("unknown tile layer {}", layer)))?;
+        let mut tile_metadata = TileMetadata::default();
+        let value_count = u16::from_le_bytes(read_array(reader)?);
+        for _ in 0..value_count {
+            let key_length = u16::from_le_bytes(read_array(reader)?) as usize;
+            let key = read_string(reader, key_length)?;
+            let [tag] = read_array(reader)?;
+            let value = match tag {
+                METADATA_INTEGER => MetadataValue::Integer(i64::from_le_bytes(read_array(reader)?)),
+                METADATA_TEXT => {
+                    let text_length = u32::from_le_bytes(read_array(reader)?) as usize;
+                    MetadataValue::Text(read_string(reader, text_length)?)
+                }
+                _ => {
+                    return Err(invalid_data(&format!(
+                        "unknown metadata value type {}",
+                        tag
+                    )))
+                }
+            };
+            tile_metadata.values.insert(key, value);
+        }
+        metadata.insert(layer, local_index as usize, tile_metadata);
+    }
+    Ok(metadata)
+}
+
+// Reads palette encoded tiles, checking they are registered. TileId::RESERVED is let through
+// for layers that can have empty spots.
+fn read_paletted_tiles<R: Read>(
+    reader: &mut R,
+    allow_empty: bool,
+) -> io::Result<[TileId; TILES_PER_CHUNK]> {
+    let paletted = PalettedTiles::read_from(reader)?;
+    let is_valid = |tile: &TileId| {
+        tile_registry().contains(*tile) || (allow_empty && *tile == TileId::RESERVED)
+    };
+    if let Some(tile) = paletted.palette.iter().find(|tile| !is_valid(tile)) {
+        return Err(invalid_data(&format!("unknown tile {}", tile.0)));
+    }
+    Ok(paletted.decode())
+}
+
+// Reads a tile id, checking the tile is registered
+fn read_tile_id<R: Read>(reader: &mut R) -> io::Result<TileId> {
+    let tile = TileId(u16::from_le_bytes(read_array(reader)?));
+    if !tile_registry().contains(tile) {
+        return Err(invalid_data(&format!("unknown tile {}", tile.0)));
+    }
+    Ok(tile)
+}
+
+// Perlin settings layout: band count (u32), every band as min height (f64) and tile id (u16),
+// then height scale factor (f64), octaves (u32), frequency, lacunarity and persistence (f64),
+// then decoration count (u32) and every decoration as tile id (u16), id of the tile it's placed
+// on (u16) and density (f64)
+fn write_perlin_settings<W: Write>(
+    writer: &mut W,
+    settings: &PerlinWorldSettings,
+) -> io::Result<()> {
+    writer.write_all(&(settings.height_bands.len() as u32).to_le_bytes())?;
+    for band in &settings.height_bands {
+        writer.write_all(&band.min_height.to_le_bytes())?;
+        writer.write_all(&band.tile.0.to_le_bytes())?;
+    }
+    writer.write_all(&settings.height_scale_factor.to_le_bytes())?;
+    writer.write_all(&(settings.octaves as u32).to_le_bytes())?;
+    for value in [
+        settings.frequency,
+        settings.lacunarity,
+        settings.persistence,
+    ] {
+        writer.write_all(&value.to_le_bytes())?;
+    }
+    writer.write_all(&(settings.decorations.len() as u32).to_le_bytes())?;
+    for decoration in &settings.decorations {
+        writer.write_all(&decoration.tile.0.to_le_bytes())?;
+        writer.write_all(&decoration.on.0.to_le_bytes())?;
+        writer.write_all(&decoration.density.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_perlin_settings<R: Read>(reader: &mut R) -> io::Result<PerlinWorldSettings> {
+    let band_count = u32::from_le_bytes(read_array(reader)?);
+    let mut height_bands = Vec::new();
+    for _ in 0..band_count {
+        let min_height = read_f64(reader)?;
+        let tile = read_tile_id(reader)?;
+        height_bands.push(HeightBand { min_height, tile });
+    }
+
+    let settings = PerlinWorldSettings {
+        height_bands,
+        height_scale_factor: read_f64(reader)?,
+        octaves: u32::from_le_bytes(read_array(reader)?) as usize,
+        frequency: read_f64(reader)?,
+        lacunarity: read_f64(reader)?,
+        persistence: read_f64(reader)?,
+        decorations: read_decorations(reader)?,
+    };
+    settings.validate().map_err(|err| invalid_data(&err))?;
+    Ok(settings)
+}
+
+fn read_decorations<R: Read>(reader: &mut R) -> io::Result<Vec<DecorationRule>> {
+    let decoration_count = u32::from_le_bytes(read_array(reader)?);
+    let mut decorations = Vec::new();
+    for _ in 0..decoration_count {
+        decorations.push(DecorationRule {
+            tile: read_tile_id(reader)?,
+            on: read_tile_id(reader)?,
+            density: read_f64(reader)?,
+        });
+    }
+    Ok(decorations)
+}
+
+fn read_string<R: Read>(reader: &mut R, length: usize) -> io::Result<String> {
+    let mut bytes = Vec::new();
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    String::from_utf8(bytes).map_err(|_| invalid_data("text is not valid utf8"))
+}
+
+fn read_f64<R: Read>(reader: &mut R) -> io::Result<f64> {
+    Ok(f64::from_le_bytes(read_array(reader)?))
+}
+
+fn read_array<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
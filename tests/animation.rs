@@ -0,0 +1,54 @@
+use world_renderer::animation::*;
+use world_renderer::world::*;
+
+fn animation(phase_offset: bool) -> TileAnimation {
+    TileAnimation {
+        frame_durations: vec![0.5, 0.25, 0.25],
+        phase_offset,
+    }
+}
+
+#[test]
+fn frames_follow_their_durations_across_cycles() {
+    let animation = animation(false);
+    let pos = GlobalTilePos(3, 4);
+    assert_eq!(animation.duration(), 1.0);
+    let frames: Vec<usize> = [0.0, 0.49, 0.5, 0.74, 0.75, 0.99]
+        .iter()
+        .map(|time| animation.frame_at(*time, &pos))
+        .collect();
+    assert_eq!(frames, vec![0, 0, 1, 1, 2, 2]);
+
+    // The next cycle starts over, and so do times before the first one
+    assert_eq!(animation.frame_at(1.0, &pos), 0);
+    assert_eq!(animation.frame_at(1.6, &pos), 1);
+    assert_eq!(animation.frame_at(7.8, &pos), 2);
+    assert_eq!(animation.frame_at(-0.1, &pos), 2);
+    // Without a phase offset every tile shows the same frame
+    assert_eq!(animation.frame_at(1.6, &GlobalTilePos(-40, 9)), 1);
+}
+
+#[test]
+fn phase_offsets_shift_neighboring_tiles() {
+    let animation = animation(true);
+    let phases = |pos: GlobalTilePos| -> Vec<usize> {
+        (0..20)
+            .map(|step| animation.frame_at(step as f32 * 0.05, &pos))
+            .collect()
+    };
+    let origin = phases(GlobalTilePos(0, 0));
+    for neighbor in [
+        GlobalTilePos(1, 0),
+        GlobalTilePos(0, 1),
+        GlobalTilePos(-1, 0),
+        GlobalTilePos(0, -1),
+    ] {
+        assert_ne!(phases(neighbor), origin, "{:?}", neighbor);
+    }
+    // The offset is fixed per tile, so a tile keeps its timing
+    assert_eq!(phases(GlobalTilePos(0, 0)), origin);
+    assert_eq!(
+        animation.frame_at(0.3, &GlobalTilePos(5, 5)),
+        animation.frame_at(1.3, &GlobalTilePos(5, 5))
+    );
+}
@@ -0,0 +1,57 @@
+use world_renderer::assets::atlas_lookup::{self, TILE_SIZE};
+use world_renderer::autotile::*;
+use world_renderer::world::*;
+
+#[test]
+fn transitions_depend_on_the_neighbor_type() {
+    let mut world = World::new();
+    world
+        .chunks
+        .insert(ChunkPos { x: 0, y: 0 }, Chunk::filled(TileId::SAND));
+    world
+        .chunks
+        .insert(ChunkPos { x: 1, y: 0 }, Chunk::filled(TileId::GRASS));
+    world.set_layer_tile(
+        &GlobalTilePos(15, 5),
+        TileLayer::Terrain,
+        Some(TileId::WATER),
+    );
+
+    // Grass in the next chunk spreads over the sand, which spreads over the water
+    assert_eq!(
+        world.transitions(&GlobalTilePos(15, 4)),
+        vec![(transition_sprite(NEIGHBOR_EAST), TileId::GRASS)]
+    );
+    assert_eq!(
+        world.transitions(&GlobalTilePos(15, 5)),
+        vec![
+            (transition_sprite(NEIGHBOR_NORTH), TileId::SAND),
+            (transition_sprite(NEIGHBOR_SOUTH), TileId::SAND),
+            (transition_sprite(NEIGHBOR_WEST), TileId::SAND),
+            (transition_sprite(NEIGHBOR_EAST), TileId::GRASS),
+        ]
+    );
+    assert!(world.transitions(&GlobalTilePos(16, 5)).is_empty());
+    assert!(world.transitions(&GlobalTilePos(3, 3)).is_empty());
+}
+
+#[test]
+fn transition_sprites_fade_out_from_their_side() {
+    let atlas = load_autotile_atlas();
+    let sprite = atlas_lookup::autotile_rect(&TileId::GRASS, NEIGHBOR_ALL, 0);
+    let transition =
+        atlas_lookup::autotile_rect(&TileId::GRASS, transition_sprite(NEIGHBOR_WEST), 0);
+    let pixel = |rect: &world_renderer::assets::AtlasRect, x: u32, y: u32| {
+        *atlas.get_pixel(rect.x as u32 + x, rect.y as u32 + y)
+    };
+    for y in 0..TILE_SIZE as u32 {
+        let alphas: Vec<u8> = (0..TILE_SIZE as u32)
+            .map(|x| pixel(&transition, x, y).0[3])
+            .collect();
+        assert!(alphas.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(alphas[0] > 0);
+        assert_eq!(alphas[TILE_SIZE as usize - 1], 0);
+        // The colors are the tile's own
+        assert_eq!(pixel(&transition, 0, y).0[..3], pixel(&sprite, 0, y).0[..3]);
+    }
+}
@@ -0,0 +1,176 @@
+mod common;
+
+use std::collections::HashSet;
+
+use common::*;
+use proptest::prelude::*;
+use world_renderer::editor_tools::*;
+use world_renderer::world::*;
+
+fn tile_set(tiles: &[GlobalTilePos]) -> HashSet<GlobalTilePos> {
+    tiles.iter().copied().collect()
+}
+
+#[test]
+fn brushes_cover_their_radius() {
+    let center = GlobalTilePos(10, -4);
+    assert_eq!(brush(BrushShape::Circle, &center, 0), vec![center]);
+    assert_eq!(brush(BrushShape::Square, &center, 0), vec![center]);
+    assert_eq!(brush(BrushShape::Square, &center, 2).len(), 25);
+
+    // A radius 1 circle is a plus shape plus the corners, radius 2 leaves the corners out
+    assert_eq!(brush(BrushShape::Circle, &center, 1).len(), 9);
+    let circle = tile_set(&brush(BrushShape::Circle, &center, 2));
+    assert_eq!(circle.len(), 21);
+    assert!(circle.contains(&GlobalTilePos(12, -4)));
+    assert!(circle.contains(&GlobalTilePos(12, -3)));
+    assert!(!circle.contains(&GlobalTilePos(12, -2)));
+}
+
+#[test]
+fn brush_strokes_leave_no_gaps() {
+    let stroke = brush_stroke(
+        BrushShape::Square,
+        &GlobalTilePos(0, 0),
+        &GlobalTilePos(10, 0),
+        1,
+    );
+    // Every tile once
+    assert_eq!(stroke.len(), tile_set(&stroke).len());
+    assert_eq!(
+        tile_set(&stroke),
+        tile_set(&rectangle(
+            &GlobalTilePos(-1, -1),
+            &GlobalTilePos(11, 1),
+            true
+        ))
+    );
+}
+
+#[test]
+fn rectangles_outline_or_fill() {
+    let (a, b) = (GlobalTilePos(3, 5), GlobalTilePos(-1, 2));
+    let filled = tile_set(&rectangle(&a, &b, true));
+    assert_eq!(filled.len(), 5 * 4);
+    let outline = tile_set(&rectangle(&a, &b, false));
+    assert_eq!(outline.len(), 5 * 4 - 3 * 2);
+    assert!(outline.is_subset(&filled));
+    assert!(!outline.contains(&GlobalTilePos(1, 3)));
+    // Corners can be given in either order
+    assert_eq!(tile_set(&rectangle(&b, &a, false)), outline);
+}
+
+#[test]
+fn ellipses_fit_their_bounds() {
+    let (a, b) = (GlobalTilePos(0, 0), GlobalTilePos(10, 6));
+    let filled = tile_set(&ellipse(&a, &b, true));
+    let outline = tile_set(&ellipse(&a, &b, false));
+    assert!(outline.is_subset(&filled));
+    assert!(filled
+        .iter()
+        .all(|pos| (0..=10).contains(&pos.0) && (0..=6).contains(&pos.1)));
+    // It touches every side of its bounds at the middle, but not the corners
+    for pos in [(5, 0), (5, 6), (0, 3), (10, 3)] {
+        assert!(outline.contains(&GlobalTilePos(pos.0, pos.1)));
+    }
+    assert!(!filled.contains(&GlobalTilePos(0, 0)));
+    assert!(!outline.contains(&GlobalTilePos(5, 3)));
+    assert!(filled.contains(&GlobalTilePos(5, 3)));
+
+    // Thin ellipses are all outline
+    let thin = ellipse(&GlobalTilePos(0, 0), &GlobalTilePos(0, 4), false);
+    assert_eq!(tile_set(&thin).len(), 5);
+}
+
+#[test]
+fn flood_fill_stops_at_other_tiles() {
+    let mut world = grass_world();
+    // A sand wall along x = 4 closes off the left of the chunk
+    for y in 0..CHUNK_SIZE {
+        world.set_layer_tile(&GlobalTilePos(4, y), TileLayer::Terrain, Some(TileId::SAND));
+    }
+    let fill = flood_fill(
+        &world,
+        &GlobalTilePos(1, 1),
+        TileLayer::Terrain,
+        MAX_FLOOD_FILL_TILES,
+    )
+    .unwrap();
+    assert_eq!(fill.len(), 4 * CHUNK_SIZE as usize);
+    assert!(fill.iter().all(|pos| pos.0 < 4));
+
+    // The wall itself fills along its length only
+    let wall = flood_fill(
+        &world,
+        &GlobalTilePos(4, 0),
+        TileLayer::Terrain,
+        MAX_FLOOD_FILL_TILES,
+    )
+    .unwrap();
+    assert_eq!(wall.len(), CHUNK_SIZE as usize);
+}
+
+#[test]
+fn flood_fill_stays_in_loaded_chunks() {
+    let world = grass_world();
+    let fill = flood_fill(
+        &world,
+        &GlobalTilePos(0, 0),
+        TileLayer::Terrain,
+        MAX_FLOOD_FILL_TILES,
+    )
+    .unwrap();
+    assert_eq!(fill.len(), TILES_PER_CHUNK);
+    assert!(flood_fill(&world, &GlobalTilePos(-1, 0), TileLayer::Terrain, 1000).is_none());
+    // Areas past the limit aren't filled at all
+    assert!(flood_fill(&world, &GlobalTilePos(0, 0), TileLayer::Terrain, 100).is_none());
+}
+
+#[test]
+fn flood_fill_in_overlays_fills_empty_space() {
+    let mut world = grass_world();
+    world.set_layer_tile(&GlobalTilePos(2, 2), TileLayer::Object, Some(TileId::TREE));
+    let empty = flood_fill(
+        &world,
+        &GlobalTilePos(0, 0),
+        TileLayer::Object,
+        MAX_FLOOD_FILL_TILES,
+    )
+    .unwrap();
+    assert_eq!(empty.len(), TILES_PER_CHUNK - 1);
+    let tree = flood_fill(
+        &world,
+        &GlobalTilePos(2, 2),
+        TileLayer::Object,
+        MAX_FLOOD_FILL_TILES,
+    )
+    .unwrap();
+    assert_eq!(tree, vec![GlobalTilePos(2, 2)]);
+}
+
+#[test]
+fn eyedropper_picks_the_top_tile() {
+    let mut world = grass_world();
+    let pos = GlobalTilePos(7, 7);
+    assert_eq!(pick_tile(&world, &pos), Some(TileId::GRASS));
+    world.set_layer_tile(&pos, TileLayer::Decoration, Some(TileId::FLOWERS));
+    assert_eq!(pick_tile(&world, &pos), Some(TileId::FLOWERS));
+    world.set_layer_tile(&pos, TileLayer::Object, Some(TileId::ROCK));
+    assert_eq!(pick_tile(&world, &pos), Some(TileId::ROCK));
+    assert_eq!(pick_tile(&world, &GlobalTilePos(-5, 0)), None);
+}
+
+proptest! {
+    #[test]
+    fn lines_are_connected(x0 in -50..50, y0 in -50..50, x1 in -50..50, y1 in -50..50) {
+        let (start, end) = (GlobalTilePos(x0, y0), GlobalTilePos(x1, y1));
+        let tiles = line(&start, &end);
+        prop_assert_eq!(tiles[0], start);
+        prop_assert_eq!(*tiles.last().unwrap(), end);
+        // One tile per step along the longer axis, each touching the last
+        prop_assert_eq!(tiles.len() as i32, (x1 - x0).abs().max((y1 - y0).abs()) + 1);
+        for step in tiles.windows(2) {
+            prop_assert!((step[1].0 - step[0].0).abs() <= 1 && (step[1].1 - step[0].1).abs() <= 1);
+        }
+    }
+}
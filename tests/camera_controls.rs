@@ -0,0 +1,147 @@
+#![cfg(feature = "viewer")]
+
+use macroquad::prelude::*;
+use world_renderer::viewer::camera::*;
+use world_renderer::viewer::input_map::*;
+
+const SCREEN_SIZE: Vec2 = Vec2::new(800.0, 600.0);
+const FRAME_TIME: f32 = 1.0 / 60.0;
+const MOUSE_MIDDLE: Input = Input::Mouse(MouseButton::Middle);
+
+fn wheel(amount: f32) -> InputSnapshot {
+    InputSnapshot {
+        wheel: amount,
+        ..Default::default()
+    }
+}
+
+// Controls at a zoom of 4 looking at (10, 20), after a frame without input has set the camera up
+fn setup() -> (CameraControls, Camera2D) {
+    let mut controls = CameraControls::new(4.0);
+    let mut camera = Camera2D {
+        target: vec2(10.0, 20.0),
+        ..Default::default()
+    };
+    controls.handle_input(
+        &mut camera,
+        &ActionState::default(),
+        Vec2::ZERO,
+        SCREEN_SIZE,
+        FRAME_TIME,
+    );
+    (controls, camera)
+}
+
+fn assert_close(a: Vec2, b: Vec2) {
+    assert!(a.distance(b) < 0.01, "{} is not {}", a, b);
+}
+
+#[test]
+fn wheel_zoom_keeps_the_point_under_the_mouse() {
+    let (mut controls, mut camera) = setup();
+    let input_map = InputMap::builtin();
+    let mouse = vec2(100.0, 450.0);
+    let grabbed = screen_to_world(&camera, mouse, SCREEN_SIZE);
+
+    for frame in 0..60 {
+        let snapshot = if frame == 0 { wheel(1.0) } else { wheel(0.0) };
+        let actions = input_map.actions(&snapshot);
+        controls.handle_input(&mut camera, &actions, mouse, SCREEN_SIZE, FRAME_TIME);
+        assert_close(screen_to_world(&camera, mouse, SCREEN_SIZE), grabbed);
+    }
+    // Wheel up zooms in, easing all the way to the target within a second
+    assert_eq!(controls.zoom, 5.0);
+    assert_eq!(camera.zoom, 5.0 / SCREEN_SIZE);
+}
+
+#[test]
+fn wheel_zoom_does_not_depend_on_the_wheel_delta() {
+    let input_map = InputMap::builtin();
+    let target_zoom = |amount: f32| {
+        let (mut controls, mut camera) = setup();
+        let actions = input_map.actions(&wheel(amount));
+        controls.handle_input(&mut camera, &actions, Vec2::ZERO, SCREEN_SIZE, FRAME_TIME);
+        controls.target_zoom
+    };
+    assert_eq!(target_zoom(1.0), 5.0);
+    assert_eq!(target_zoom(120.0), 5.0);
+    assert_eq!(target_zoom(-120.0), 3.2);
+    // Touchpads scroll by fractions of a notch
+    assert!((target_zoom(0.5) - 4.0 * 1.25f32.sqrt()).abs() < 0.001);
+}
+
+#[test]
+fn zoom_eases_the_same_at_any_frame_rate() {
+    let input_map = InputMap::builtin();
+    let zoom_after = |frames: u32, frame_time: f32| {
+        let (mut controls, mut camera) = setup();
+        for frame in 0..frames {
+            let snapshot = if frame == 0 { wheel(-1.0) } else { wheel(0.0) };
+            let actions = input_map.actions(&snapshot);
+            controls.handle_input(&mut camera, &actions, Vec2::ZERO, SCREEN_SIZE, frame_time);
+        }
+        controls.zoom
+    };
+    let zoom = zoom_after(6, 1.0 / 60.0);
+    assert!(zoom < 4.0 && zoom > 3.2);
+    assert!((zoom_after(3, 1.0 / 30.0) - zoom).abs() < 0.001);
+    assert!((zoom_after(12, 1.0 / 120.0) - zoom).abs() < 0.001);
+}
+
+#[test]
+fn zoom_stays_in_range() {
+    let (mut controls, mut camera) = setup();
+    let input_map = InputMap::builtin();
+    for _ in 0..100 {
+        let actions = input_map.actions(&wheel(1.0));
+        controls.handle_input(&mut camera, &actions, Vec2::ZERO, SCREEN_SIZE, FRAME_TIME);
+    }
+    assert_eq!(controls.target_zoom, MAX_ZOOM);
+    assert!(controls.zoom <= MAX_ZOOM);
+    assert_eq!(CameraControls::new(0.1).zoom, MIN_ZOOM);
+}
+
+#[test]
+fn dragging_keeps_the_grabbed_point_under_the_mouse() {
+    let (mut controls, mut camera) = setup();
+    let input_map = InputMap::builtin();
+    let start = vec2(400.0, 300.0);
+    let grabbed = screen_to_world(&camera, start, SCREEN_SIZE);
+
+    let frames = [
+        (InputSnapshot::pressing(&[MOUSE_MIDDLE]), start),
+        (InputSnapshot::holding(&[MOUSE_MIDDLE]), vec2(300.0, 250.0)),
+        (InputSnapshot::holding(&[MOUSE_MIDDLE]), vec2(50.0, 500.0)),
+    ];
+    for (snapshot, mouse) in &frames {
+        let actions = input_map.actions(snapshot);
+        controls.handle_input(&mut camera, &actions, *mouse, SCREEN_SIZE, FRAME_TIME);
+        assert_close(screen_to_world(&camera, *mouse, SCREEN_SIZE), grabbed);
+    }
+
+    // Zooming while dragging keeps the grabbed point too
+    let mut snapshot = InputSnapshot::holding(&[MOUSE_MIDDLE]);
+    snapshot.wheel = 1.0;
+    let mouse = vec2(60.0, 480.0);
+    controls.handle_input(
+        &mut camera,
+        &input_map.actions(&snapshot),
+        mouse,
+        SCREEN_SIZE,
+        FRAME_TIME,
+    );
+    assert!(controls.zoom > 4.0);
+    assert_close(screen_to_world(&camera, mouse, SCREEN_SIZE), grabbed);
+
+    // Once released, moving the mouse leaves the camera alone
+    controls.target_zoom = controls.zoom;
+    let target = camera.target;
+    controls.handle_input(
+        &mut camera,
+        &ActionState::default(),
+        vec2(700.0, 100.0),
+        SCREEN_SIZE,
+        FRAME_TIME,
+    );
+    assert_close(camera.target, target);
+}
@@ -0,0 +1,76 @@
+use noise::utils::{NoiseMapBuilder, PlaneMapBuilder};
+use noise::{Fbm, MultiFractal, Perlin};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use world_renderer::world::*;
+use world_renderer::world_generation::PerlinChunkGenerator;
+
+#[test]
+fn chunks_generated_out_of_order_match_chunks_generated_in_sequence() {
+    let settings = PerlinWorldSettings::default();
+    let chunk_positions: Vec<ChunkPos> = (-3..3)
+        .flat_map(|y| (-3..3).map(move |x| ChunkPos { x, y }))
+        .collect();
+    let sequential = PerlinChunkGenerator::new(9, WorldIslandSize::Small, settings.clone());
+    let expected: Vec<Chunk> = chunk_positions
+        .iter()
+        .map(|chunk_pos| sequential.generate_chunk(chunk_pos))
+        .collect();
+
+    let mut shuffled = chunk_positions.clone();
+    shuffled.shuffle(&mut StdRng::seed_from_u64(4));
+    let generator = PerlinChunkGenerator::new(9, WorldIslandSize::Small, settings);
+    for chunk_pos in &shuffled {
+        let index = chunk_positions
+            .iter()
+            .position(|pos| pos == chunk_pos)
+            .unwrap();
+        let chunk = generator.generate_chunk(chunk_pos);
+        assert_eq!(
+            chunk.tiles, expected[index].tiles,
+            "terrain of {:?}",
+            chunk_pos
+        );
+        assert_eq!(chunk.decorations, expected[index].decorations);
+        assert_eq!(chunk.objects, expected[index].objects);
+    }
+}
+
+#[test]
+fn finite_worlds_sample_like_a_plane_map() {
+    let settings = PerlinWorldSettings {
+        decorations: Vec::new(),
+        octaves: 3,
+        frequency: 1.7,
+        ..PerlinWorldSettings::default()
+    };
+    let (size, island_size, seed) = (4, WorldIslandSize::Medium, 12);
+    let generator =
+        PerlinChunkGenerator::for_finite_world(seed, size, island_size, settings.clone());
+
+    let fbm = Fbm::<Perlin>::new(seed)
+        .set_octaves(settings.octaves)
+        .set_frequency(settings.frequency);
+    let bound = size as f64 / island_size as i64 as f64;
+    let tiles = size as usize * 16;
+    let plane = PlaneMapBuilder::<_, 2>::new(&fbm)
+        .set_size(tiles, tiles)
+        .set_x_bounds(-bound, bound)
+        .set_y_bounds(-bound, bound)
+        .build();
+    for chunk_y in 0..size {
+        for chunk_x in 0..size {
+            let chunk_pos = ChunkPos {
+                x: chunk_x,
+                y: chunk_y,
+            };
+            let chunk = generator.generate_chunk(&chunk_pos);
+            for (pos, tile) in chunk.iter_tiles(&chunk_pos) {
+                let height =
+                    settings.height_scale_factor * plane.get_value(pos.0 as usize, pos.1 as usize);
+                assert_eq!(*tile, settings.tile_at_height(height), "tile {:?}", pos);
+            }
+        }
+    }
+}
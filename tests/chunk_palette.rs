@@ -0,0 +1,92 @@
+use proptest::prelude::*;
+use world_renderer::chunk_palette::PalettedTiles;
+use world_renderer::world::*;
+
+// Chunks drawing from a palette of up to palette_size different tiles, covering every index size
+fn chunk_tiles(palette_size: u16) -> impl Strategy<Value = [TileId; TILES_PER_CHUNK]> {
+    proptest::collection::vec(0..palette_size, TILES_PER_CHUNK).prop_map(|ids| {
+        let mut tiles = [TileId(0); TILES_PER_CHUNK];
+        for (tile, id) in tiles.iter_mut().zip(ids) {
+            *tile = TileId(id * 3); // Spread out so palette indices and ids differ
+        }
+        tiles
+    })
+}
+
+fn any_chunk_tiles() -> impl Strategy<Value = [TileId; TILES_PER_CHUNK]> {
+    prop_oneof![
+        chunk_tiles(2),
+        chunk_tiles(4),
+        chunk_tiles(16),
+        chunk_tiles(300)
+    ]
+}
+
+#[test]
+fn single_tile_chunks_take_a_few_bytes() {
+    let paletted = PalettedTiles::encode(&[TileId::WATER; TILES_PER_CHUNK]);
+    assert_eq!(paletted.to_bytes().len(), 4);
+    assert_eq!(paletted.get(200), TileId::WATER);
+}
+
+#[test]
+fn single_tile_chunks_store_one_tile_in_memory() {
+    assert!(std::mem::size_of::<TerrainTiles>() <= 16);
+    let mut world = World::new();
+    let chunk_pos = ChunkPos { x: 0, y: 0 };
+    world.chunks.insert(chunk_pos, Chunk::filled(TileId::WATER));
+    assert!(matches!(
+        world.chunks[&chunk_pos].tiles,
+        TerrainTiles::Uniform(TileId::WATER)
+    ));
+
+    let pos = GlobalTilePos(3, 4);
+    world.set_layer_tile(&pos, TileLayer::Terrain, Some(TileId::SAND));
+    assert!(matches!(
+        world.chunks[&chunk_pos].tiles,
+        TerrainTiles::Mixed(_)
+    ));
+    assert_eq!(world.loaded_tile(&pos), Some(&TileId::SAND));
+    assert_eq!(
+        world.loaded_tile(&GlobalTilePos(4, 4)),
+        Some(&TileId::WATER)
+    );
+
+    // Back to a single tile
+    world.set_layer_tile(&pos, TileLayer::Terrain, Some(TileId::WATER));
+    assert_eq!(
+        world.chunks[&chunk_pos].tiles,
+        TerrainTiles::Uniform(TileId::WATER)
+    );
+    assert_eq!(
+        Chunk::new([TileId::WATER; TILES_PER_CHUNK]).tiles,
+        TerrainTiles::Mixed(Box::new([TileId::WATER; TILES_PER_CHUNK]))
+    );
+}
+
+#[test]
+fn indices_past_the_palette_are_rejected() {
+    let mut tiles = [TileId::GRASS; TILES_PER_CHUNK];
+    tiles[1] = TileId::SAND;
+    tiles[2] = TileId::SNOW;
+    let mut bytes = PalettedTiles::encode(&tiles).to_bytes();
+    // Three tiles use two bit indices, index 3 is past the end of the palette
+    *bytes.last_mut().unwrap() = 0b1100_0000;
+    assert!(PalettedTiles::read_from(&mut bytes.as_slice()).is_err());
+}
+
+proptest! {
+    #[test]
+    fn encoding_round_trips(tiles in any_chunk_tiles()) {
+        let paletted = PalettedTiles::encode(&tiles);
+        prop_assert_eq!(paletted.decode(), tiles);
+        for (local_index, tile) in tiles.iter().enumerate() {
+            prop_assert_eq!(paletted.get(local_index), *tile);
+        }
+
+        let bytes = paletted.to_bytes();
+        prop_assert_eq!(bytes.len(), paletted.encoded_len());
+        let read = PalettedTiles::read_from(&mut bytes.as_slice()).unwrap();
+        prop_assert_eq!(read.decode(), tiles);
+    }
+}
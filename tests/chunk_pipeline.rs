@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+use world_renderer::world::*;
+
+#[test]
+fn background_generation_matches_generating_in_sequence() {
+    let generated = World::new().generate_world(
+        WorldGenerationType::PerlinTerrain,
+        WorldGenerationSize::Small,
+        WorldIslandSize::Small,
+        21,
+    );
+    let mut background = World::new().generate_world_in_background(
+        WorldGenerationType::PerlinTerrain,
+        WorldGenerationSize::Small,
+        WorldIslandSize::Small,
+        21,
+    );
+    let started = Instant::now();
+    while background.chunks.len() < generated.chunks.len() {
+        assert!(started.elapsed() < Duration::from_secs(30));
+        background.receive_generated_chunks();
+        std::thread::sleep(Duration::from_millis(5));
+    }
+
+    assert_eq!(background.chunks.len(), generated.chunks.len());
+    for (chunk_pos, chunk) in &generated.chunks {
+        let background_chunk = &background.chunks[chunk_pos];
+        assert_eq!(
+            background_chunk.tiles, chunk.tiles,
+            "terrain of {:?}",
+            chunk_pos
+        );
+        assert_eq!(background_chunk.decorations, chunk.decorations);
+        assert_eq!(background_chunk.objects, chunk.objects);
+    }
+}
+
+#[test]
+fn dropping_a_pipeline_skips_the_queued_chunks() {
+    // Generating the whole world takes far longer than the workers' current chunks
+    let world = World::new().generate_world_in_background(
+        WorldGenerationType::PerlinTerrain,
+        WorldGenerationSize::Titanic,
+        WorldIslandSize::Small,
+        21,
+    );
+    let started = Instant::now();
+    drop(world);
+    assert!(started.elapsed() < Duration::from_secs(2));
+}
@@ -0,0 +1,26 @@
+// Fixtures shared by the integration tests. Not every test file uses all of them.
+#![allow(dead_code)]
+
+use world_renderer::world::*;
+
+// A single grass chunk at the origin
+pub fn grass_world() -> World {
+    let mut world = World::new();
+    world
+        .chunks
+        .insert(ChunkPos { x: 0, y: 0 }, Chunk::filled(TileId::GRASS));
+    world
+}
+
+// Grass chunks around the origin, so edits can cross chunk boundaries
+pub fn grass_world_around_origin() -> World {
+    let mut world = World::new();
+    for y in -1..=0 {
+        for x in -1..=0 {
+            world
+                .chunks
+                .insert(ChunkPos { x, y }, Chunk::filled(TileId::GRASS));
+        }
+    }
+    world
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use common::*;
+use world_renderer::edit_history::EditHistory;
+use world_renderer::tile_metadata::{MetadataValue, TileMetadata, TEXT};
+use world_renderer::world::*;
+
+fn terrain(world: &World, x: i32, y: i32) -> TileId {
+    *world.loaded_tile(&GlobalTilePos(x, y)).unwrap()
+}
+
+#[test]
+fn strokes_undo_and_redo_as_one_action() {
+    let mut world = grass_world();
+    let mut history = EditHistory::new(10);
+    history.begin_stroke();
+    for x in 0..3 {
+        history.apply(
+            &mut world,
+            &GlobalTilePos(x, 0),
+            TileLayer::Terrain,
+            Some(TileId::SAND),
+        );
+    }
+    // Painting over the stroke's own tiles reverts to the tile from before the stroke
+    history.apply(
+        &mut world,
+        &GlobalTilePos(0, 0),
+        TileLayer::Terrain,
+        Some(TileId::WATER),
+    );
+    history.end_stroke();
+    assert_eq!(history.len(), 1);
+
+    assert!(history.undo(&mut world));
+    assert!((0..3).all(|x| terrain(&world, x, 0) == TileId::GRASS));
+    assert!(history.redo(&mut world));
+    assert_eq!(terrain(&world, 0, 0), TileId::WATER);
+    assert_eq!(terrain(&world, 2, 0), TileId::SAND);
+}
+
+#[test]
+fn edits_outside_strokes_are_single_actions() {
+    let mut world = grass_world();
+    let mut history = EditHistory::new(10);
+    history.apply(
+        &mut world,
+        &GlobalTilePos(1, 1),
+        TileLayer::Terrain,
+        Some(TileId::SNOW),
+    );
+    history.apply(
+        &mut world,
+        &GlobalTilePos(2, 1),
+        TileLayer::Object,
+        Some(TileId::TREE),
+    );
+    // Unchanged tiles aren't recorded
+    history.apply(
+        &mut world,
+        &GlobalTilePos(3, 1),
+        TileLayer::Terrain,
+        Some(TileId::GRASS),
+    );
+    assert_eq!(history.len(), 2);
+
+    history.undo(&mut world);
+    assert_eq!(
+        world.get_layer_tile(&GlobalTilePos(2, 1), TileLayer::Object),
+        None
+    );
+    assert_eq!(terrain(&world, 1, 1), TileId::SNOW);
+}
+
+#[test]
+fn new_edits_drop_the_redo_actions() {
+    let mut world = grass_world();
+    let mut history = EditHistory::new(10);
+    history.apply(
+        &mut world,
+        &GlobalTilePos(0, 0),
+        TileLayer::Terrain,
+        Some(TileId::SAND),
+    );
+    history.undo(&mut world);
+    assert!(history.can_redo());
+    history.apply(
+        &mut world,
+        &GlobalTilePos(5, 5),
+        TileLayer::Terrain,
+        Some(TileId::STONE),
+    );
+    assert!(!history.can_redo());
+    assert!(!history.redo(&mut world));
+}
+
+#[test]
+fn history_is_bounded() {
+    let mut world = grass_world();
+    let mut history = EditHistory::new(3);
+    for x in 0..5 {
+        history.apply(
+            &mut world,
+            &GlobalTilePos(x, 0),
+            TileLayer::Terrain,
+            Some(TileId::SAND),
+        );
+    }
+    assert_eq!(history.len(), 3);
+    while history.undo(&mut world) {}
+    // The two oldest edits can't be undone anymore
+    assert_eq!(terrain(&world, 0, 0), TileId::SAND);
+    assert_eq!(terrain(&world, 1, 0), TileId::SAND);
+    assert!((2..5).all(|x| terrain(&world, x, 0) == TileId::GRASS));
+}
+
+#[test]
+fn undo_restores_dropped_metadata() {
+    let mut world = grass_world();
+    let mut history = EditHistory::new(10);
+    let pos = GlobalTilePos(4, 4);
+    let mut metadata = TileMetadata::default();
+    metadata.set(TEXT, MetadataValue::Text("Home".to_string()));
+    world.set_tile_metadata(&pos, TileLayer::Terrain, metadata.clone());
+
+    history.apply(&mut world, &pos, TileLayer::Terrain, Some(TileId::WATER));
+    assert!(world.get_tile_metadata(&pos, TileLayer::Terrain).is_none());
+    history.undo(&mut world);
+    assert_eq!(
+        world.get_tile_metadata(&pos, TileLayer::Terrain),
+        Some(&metadata)
+    );
+}
@@ -0,0 +1,275 @@
+#![cfg(feature = "viewer")]
+
+mod common;
+
+use common::*;
+use macroquad::prelude::{KeyCode, MouseButton};
+use world_renderer::edit_history::EditHistory;
+use world_renderer::editor_tools::EditorTool;
+use world_renderer::viewer::editor::TileEditor;
+use world_renderer::viewer::input_map::*;
+use world_renderer::world::*;
+
+const CTRL: Input = Input::Key(KeyCode::LeftControl);
+const SHIFT: Input = Input::Key(KeyCode::LeftShift);
+const MOUSE_LEFT: Input = Input::Mouse(MouseButton::Left);
+
+fn key(key: KeyCode) -> Input {
+    Input::Key(key)
+}
+
+#[test]
+fn default_bindings_trigger_their_actions() {
+    let input_map = InputMap::builtin();
+    // Any of an action's bindings triggers it
+    for input in [key(KeyCode::W), key(KeyCode::Up)] {
+        let actions = input_map.actions(&InputSnapshot::pressing(&[input]));
+        assert!(actions.is_pressed(Action::PanUp));
+        assert!(!actions.is_down(Action::PanDown));
+    }
+
+    let held = input_map.actions(&InputSnapshot::holding(&[MOUSE_LEFT]));
+    assert!(held.is_down(Action::Paint));
+    assert!(!held.is_pressed(Action::Paint));
+    assert_eq!(held.value(Action::Paint), 1.0);
+
+    let actions = input_map.actions(&InputSnapshot::pressing(&[key(KeyCode::Key1)]));
+    assert!(actions.is_pressed(Action::PaintTile(TileId::WATER)));
+}
+
+#[test]
+fn bindings_need_exactly_their_modifiers() {
+    let input_map = InputMap::builtin();
+    let pressed_actions = |inputs: &[Input]| {
+        let mut actions: Vec<Action> = input_map
+            .actions(&InputSnapshot::pressing(inputs))
+            .pressed_actions()
+            .collect();
+        actions.sort_by_key(|action| format!("{:?}", action));
+        actions
+    };
+
+    assert_eq!(pressed_actions(&[key(KeyCode::Z)]), vec![]);
+    assert_eq!(
+        pressed_actions(&[CTRL, key(KeyCode::Z)]),
+        vec![Action::Undo]
+    );
+    assert_eq!(
+        pressed_actions(&[CTRL, SHIFT, key(KeyCode::Z)]),
+        vec![Action::Redo]
+    );
+    assert_eq!(
+        pressed_actions(&[key(KeyCode::Y)]),
+        vec![Action::FlipStampVertically]
+    );
+    assert_eq!(
+        pressed_actions(&[CTRL, key(KeyCode::Y)]),
+        vec![Action::Redo]
+    );
+    // Holding Ctrl doesn't pan with W
+    assert_eq!(pressed_actions(&[CTRL, key(KeyCode::W)]), vec![]);
+}
+
+#[test]
+fn modifier_keys_can_be_bound() {
+    let mut input_map = InputMap::default();
+    input_map.bind(Action::PanUp, vec!["LeftShift".parse().unwrap()]);
+    input_map.bind(Action::PanDown, vec!["Ctrl+LeftShift".parse().unwrap()]);
+    let actions = input_map.actions(&InputSnapshot::holding(&[SHIFT]));
+    assert!(actions.is_down(Action::PanUp));
+    assert!(!actions.is_down(Action::PanDown));
+    let actions = input_map.actions(&InputSnapshot::holding(&[CTRL, SHIFT]));
+    assert!(actions.is_down(Action::PanDown));
+    assert!(!actions.is_down(Action::PanUp));
+}
+
+#[test]
+fn bindings_files_replace_the_actions_they_list() {
+    let mut input_map = InputMap::builtin();
+    input_map
+        .add_from_toml_str(
+            r#"
+            PanUp = ["I", "alt+up"]
+            Undo = []
+            "PaintTile(Snow)" = ["Key1"]
+            "#,
+        )
+        .unwrap();
+    assert_eq!(
+        input_map.bindings(Action::PanUp),
+        &["I".parse().unwrap(), "Alt+Up".parse().unwrap()]
+    );
+    assert!(input_map.bindings(Action::Undo).is_empty());
+    // Unlisted actions keep their default bindings, even when they share an input
+    assert_eq!(
+        input_map.bindings(Action::EyedropperTool),
+        &["I".parse().unwrap()]
+    );
+    let actions = input_map.actions(&InputSnapshot::pressing(&[key(KeyCode::Key1)]));
+    assert!(actions.is_pressed(Action::PaintTile(TileId::SNOW)));
+    assert!(actions.is_pressed(Action::PaintTile(TileId::WATER)));
+}
+
+#[test]
+fn invalid_bindings_files_change_nothing() {
+    let builtin = InputMap::builtin();
+    for text in [
+        "PanUp = [\"W\"]\nJump = [\"Space\"]",
+        "PanUp = [\"W\", \"Hyper+W\"]",
+        "PanUp = [\"NotAKey\"]",
+        "\"PaintTile(Lava)\" = [\"Key5\"]",
+        "PanUp = \"W\"",
+    ] {
+        let mut input_map = InputMap::builtin();
+        assert!(input_map.add_from_toml_str(text).is_err(), "{}", text);
+        assert_eq!(input_map, builtin);
+    }
+    assert!(InputMap::builtin().load("missing_bindings.toml").is_err());
+}
+
+#[test]
+fn actions_and_bindings_parse() {
+    assert_eq!("pan_up".parse(), Ok(Action::PanUp));
+    assert_eq!(
+        "PaintTile(DeepWater)".parse(),
+        Ok(Action::PaintTile(TileId::DEEP_WATER))
+    );
+    assert_eq!("PaintTile(2)".parse(), Ok(Action::PaintTile(TileId::SAND)));
+    assert!("PaintTile(4000)".parse::<Action>().is_err());
+
+    for text in ["Ctrl+Shift+Z", "Alt+MouseMiddle", "WheelUp", "F5"] {
+        let binding: Binding = text.parse().unwrap();
+        assert_eq!(binding.to_string(), text);
+    }
+    assert_eq!(
+        "control + z".parse::<Binding>().unwrap().to_string(),
+        "Ctrl+Z"
+    );
+}
+
+#[test]
+fn wheel_bindings_carry_how_far_it_moved() {
+    let input_map = InputMap::builtin();
+    let actions = input_map.actions(&InputSnapshot {
+        wheel: 2.0,
+        ..Default::default()
+    });
+    assert!(actions.is_pressed(Action::ZoomIn));
+    assert_eq!(actions.value(Action::ZoomIn), 2.0);
+    assert!(!actions.is_down(Action::ZoomOut));
+
+    let actions = input_map.actions(&InputSnapshot {
+        wheel: -0.5,
+        ..Default::default()
+    });
+    assert_eq!(actions.value(Action::ZoomOut), 0.5);
+}
+
+#[test]
+fn recorded_input_replays_through_the_editor() {
+    let mut world = grass_world();
+    let mut history = EditHistory::new(10);
+    let mut editor = TileEditor::new();
+    let input_map = InputMap::builtin();
+
+    // Picks sand, switches to the filled rectangle tool and drags from (1, 1) to (3, 2)
+    let frames = [
+        (InputSnapshot::pressing(&[key(KeyCode::Key3)]), (0, 0)),
+        (InputSnapshot::pressing(&[key(KeyCode::R)]), (0, 0)),
+        (InputSnapshot::pressing(&[key(KeyCode::R)]), (0, 0)),
+        (InputSnapshot::pressing(&[MOUSE_LEFT]), (1, 1)),
+        (InputSnapshot::holding(&[MOUSE_LEFT]), (2, 2)),
+        (InputSnapshot::default(), (3, 2)),
+    ];
+    for (snapshot, (x, y)) in &frames {
+        editor.handle_input(
+            &input_map.actions(snapshot),
+            GlobalTilePos(*x, *y),
+            &mut world,
+            &mut history,
+            false,
+        );
+    }
+    assert_eq!(editor.tool, EditorTool::Rectangle { filled: true });
+    for y in 1..=2 {
+        for x in 1..=3 {
+            assert_eq!(world.get_tile(&GlobalTilePos(x, y)), Some(&TileId::SAND));
+        }
+    }
+    assert_eq!(world.get_tile(&GlobalTilePos(4, 2)), Some(&TileId::GRASS));
+    assert_eq!(history.len(), 1);
+
+    let undo = InputSnapshot::pressing(&[CTRL, key(KeyCode::Z)]);
+    editor.handle_input(
+        &input_map.actions(&undo),
+        GlobalTilePos(0, 0),
+        &mut world,
+        &mut history,
+        false,
+    );
+    assert_eq!(world.get_tile(&GlobalTilePos(2, 2)), Some(&TileId::GRASS));
+}
+
+#[test]
+fn drags_carry_on_over_the_editor_panel() {
+    let mut world = grass_world();
+    let mut history = EditHistory::new(10);
+    let mut editor = TileEditor::new();
+    editor.tool = EditorTool::Rectangle { filled: true };
+    editor.tile = TileId::SAND;
+    let input_map = InputMap::builtin();
+
+    // Clicks on the panel don't start a drag
+    let frames = [
+        (InputSnapshot::pressing(&[MOUSE_LEFT]), (8, 8), true),
+        (InputSnapshot::holding(&[MOUSE_LEFT]), (9, 9), true),
+        (InputSnapshot::default(), (10, 10), true),
+        // Dragging from the world over the panel only places the shape on release
+        (InputSnapshot::pressing(&[MOUSE_LEFT]), (1, 1), false),
+        (InputSnapshot::holding(&[MOUSE_LEFT]), (5, 5), true),
+        (InputSnapshot::holding(&[MOUSE_LEFT]), (2, 6), true),
+        (InputSnapshot::default(), (3, 2), true),
+    ];
+    for (snapshot, (x, y), pointer_over_ui) in &frames {
+        assert!(history.is_empty());
+        editor.handle_input(
+            &input_map.actions(snapshot),
+            GlobalTilePos(*x, *y),
+            &mut world,
+            &mut history,
+            *pointer_over_ui,
+        );
+    }
+    assert_eq!(history.len(), 1);
+    let sand_tiles = (0..16)
+        .flat_map(|y| (0..16).map(move |x| GlobalTilePos(x, y)))
+        .filter(|pos| world.loaded_tile(pos) == Some(&TileId::SAND))
+        .count();
+    assert_eq!(sand_tiles, 6);
+    assert_eq!(world.loaded_tile(&GlobalTilePos(3, 2)), Some(&TileId::SAND));
+}
+
+#[test]
+fn refused_fills_leave_a_message() {
+    let mut world = World::new();
+    for y in 0..16 {
+        for x in 0..17 {
+            world
+                .chunks
+                .insert(ChunkPos { x, y }, Chunk::filled(TileId::WATER));
+        }
+    }
+    let mut history = EditHistory::new(10);
+    let mut editor = TileEditor::new();
+    editor.tool = EditorTool::FloodFill;
+    let input_map = InputMap::builtin();
+    let click = input_map.actions(&InputSnapshot::pressing(&[MOUSE_LEFT]));
+
+    editor.handle_input(&click, GlobalTilePos(0, 0), &mut world, &mut history, false);
+    assert!(editor.message.is_some());
+    assert!(history.is_empty());
+    // The next click on the world clears it
+    editor.tool = EditorTool::Eyedropper;
+    editor.handle_input(&click, GlobalTilePos(0, 0), &mut world, &mut history, false);
+    assert_eq!(editor.message, None);
+}
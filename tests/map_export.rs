@@ -0,0 +1,50 @@
+mod common;
+
+use common::*;
+use world_renderer::map_export::*;
+use world_renderer::world::*;
+
+#[test]
+fn color_maps_show_the_uppermost_tiles() {
+    let mut world = grass_world();
+    world.set_layer_tile(&GlobalTilePos(2, 3), TileLayer::Terrain, Some(TileId::SAND));
+    world.set_layer_tile(&GlobalTilePos(4, 1), TileLayer::Object, Some(TileId::TREE));
+
+    // One column left of the loaded chunk
+    let map = render_map(
+        &world,
+        &GlobalTilePos(-1, 0),
+        &GlobalTilePos(5, 4),
+        MapStyle::Colors,
+    )
+    .unwrap();
+    assert_eq!(map.dimensions(), (7, 5));
+    assert_eq!(map.get_pixel(0, 0).0, [0, 0, 0, 0]);
+    assert_eq!(map.get_pixel(1, 0).0, TileId::GRASS.map_color());
+    assert_eq!(map.get_pixel(3, 3).0, TileId::SAND.map_color());
+    assert_eq!(map.get_pixel(5, 1).0, TileId::TREE.map_color());
+
+    let sprites = render_map(
+        &world,
+        &GlobalTilePos(0, 0),
+        &GlobalTilePos(2, 1),
+        MapStyle::Sprites,
+    )
+    .unwrap();
+    assert_eq!(sprites.dimensions(), (24, 16));
+}
+
+#[test]
+fn areas_too_large_for_an_image_are_refused() {
+    let world = grass_world();
+    let min = GlobalTilePos(0, 0);
+    let edge = MAX_MAP_SIZE as i32 - 1;
+    assert!(map_size(&min, &GlobalTilePos(edge, 3), MapStyle::Colors).is_ok());
+    assert!(map_size(&min, &GlobalTilePos(edge + 1, 3), MapStyle::Colors).is_err());
+    assert!(map_size(&min, &GlobalTilePos(edge, 3), MapStyle::Sprites).is_err());
+    let everything = (
+        GlobalTilePos(i32::MIN, i32::MIN),
+        GlobalTilePos(i32::MAX, i32::MAX),
+    );
+    assert!(render_map(&world, &everything.0, &everything.1, MapStyle::Colors).is_err());
+}
@@ -0,0 +1,94 @@
+use noise::utils::{NoiseMapBuilder, PlaneMapBuilder};
+use noise::{Fbm, Perlin};
+use world_renderer::world::*;
+use world_renderer::world_preset::WorldPreset;
+
+// Tile the perlin world generation picked for a noise value before the height bands existed
+fn baseline_tile(pixel: f64) -> TileId {
+    let terrain_height_offset = 0.4;
+    let pixel = 1.9 * pixel;
+    match pixel {
+        _ if pixel >= 1.5 + terrain_height_offset => TileId::SNOW,
+        _ if pixel >= 1.1 + terrain_height_offset => TileId::DARK_STONE,
+        _ if pixel >= 0.8 + terrain_height_offset => TileId::STONE,
+        _ if pixel >= 0.29 + terrain_height_offset => TileId::GRASS,
+        _ if pixel >= 0.0 + terrain_height_offset => TileId::SAND,
+        _ if pixel >= -0.50 + terrain_height_offset => TileId::SHALLOW_WATER,
+        _ if pixel >= -1.55 + terrain_height_offset => TileId::WATER,
+        _ => TileId::DEEP_WATER,
+    }
+}
+
+#[test]
+fn default_settings_reproduce_the_baseline_terrain() {
+    let (size, island_size, seed) = (WorldGenerationSize::Small, WorldIslandSize::Small, 25);
+    let bound = size as i32 as f64 / island_size as i64 as f64;
+    let tiles = size as usize * 16;
+    let fbm = Fbm::<Perlin>::new(seed);
+    let plane = PlaneMapBuilder::<_, 2>::new(&fbm)
+        .set_size(tiles, tiles)
+        .set_x_bounds(-bound, bound)
+        .set_y_bounds(-bound, bound)
+        .build();
+
+    let settings = PerlinWorldSettings {
+        decorations: Vec::new(),
+        ..PerlinWorldSettings::default()
+    };
+    let mut world = World::new();
+    world.chunks = World::generate_perlin_noise_world(size, island_size, seed, &settings);
+    for y in 0..tiles {
+        for x in 0..tiles {
+            assert_eq!(
+                world.loaded_tile(&GlobalTilePos(x as i32, y as i32)),
+                Some(&baseline_tile(plane.get_value(x, y))),
+                "tile {}, {}",
+                x,
+                y
+            );
+        }
+    }
+}
+
+#[test]
+fn noise_settings_must_be_finite() {
+    let text = |value: &str| {
+        format!(
+            r#"
+            name = "Broken"
+            generation_type = "PerlinTerrain"
+            size = "Tiny"
+            island_size = "Small"
+            seed = 3
+
+            [perlin]
+            frequency = {}
+            "#,
+            value
+        )
+    };
+    assert!(WorldPreset::from_toml_str(&text("1.5")).is_ok());
+    for value in ["nan", "inf", "-inf"] {
+        assert!(
+            WorldPreset::from_toml_str(&text(value)).is_err(),
+            "{}",
+            value
+        );
+    }
+    for settings in [
+        PerlinWorldSettings {
+            lacunarity: f64::NAN,
+            ..PerlinWorldSettings::default()
+        },
+        PerlinWorldSettings {
+            persistence: f64::INFINITY,
+            ..PerlinWorldSettings::default()
+        },
+        PerlinWorldSettings {
+            height_scale_factor: f64::NAN,
+            ..PerlinWorldSettings::default()
+        },
+    ] {
+        assert!(settings.validate().is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use world_renderer::world::*;
+
+fn temp_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    directory
+}
+
+// View of the chunks from (x, y) to (x + 1, y + 1)
+fn chunk_view(x: i32, y: i32) -> ViewRect {
+    let size = CHUNK_SIZE as f32;
+    ViewRect {
+        left: x as f32 * size,
+        top: y as f32 * size,
+        right: (x + 2) as f32 * size,
+        bottom: (y + 2) as f32 * size,
+    }
+}
+
+fn stored_chunk(world: &World, chunk_pos: &ChunkPos) -> Option<Chunk> {
+    world
+        .region_storage
+        .as_ref()
+        .unwrap()
+        .load_chunk(chunk_pos)
+        .unwrap()
+}
+
+#[test]
+fn generated_chunks_are_written_when_evicted() {
+    let directory = temp_directory("generated_chunks_are_written");
+    let mut world = World::new().generate_world(
+        WorldGenerationType::InfinitePerlinTerrain,
+        WorldGenerationSize::Tiny,
+        WorldIslandSize::Small,
+        3,
+    );
+    world.use_region_storage(&directory).unwrap();
+
+    world.update_loaded_chunks(&chunk_view(0, 0));
+    let origin = ChunkPos { x: 0, y: 0 };
+    let generated = world.chunks[&origin].tiles.clone();
+    assert!(stored_chunk(&world, &origin).is_none());
+
+    // Far enough away for the first chunks to be evicted
+    world.update_loaded_chunks(&chunk_view(100, 100));
+    assert!(!world.chunks.contains_key(&origin));
+    assert_eq!(stored_chunk(&world, &origin).unwrap().tiles, generated);
+
+    // Reading a tile pages its chunk back in
+    assert_eq!(world.get_tile(&GlobalTilePos(0, 0)), Some(generated.get(0)));
+    assert!(world.chunks.contains_key(&origin));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn background_generated_chunks_are_written_when_evicted() {
+    let directory = temp_directory("background_chunks_are_written");
+    let mut world = World::new().generate_world_in_background(
+        WorldGenerationType::PerlinTerrain,
+        WorldGenerationSize::Tiny,
+        WorldIslandSize::Small,
+        3,
+    );
+    world.use_region_storage(&directory).unwrap();
+
+    // The view is far from every chunk, so they are evicted as soon as they arrive
+    let chunk_positions: Vec<ChunkPos> = (0..2)
+        .flat_map(|y| (0..2).map(move |x| ChunkPos { x, y }))
+        .collect();
+    let started = Instant::now();
+    while !chunk_positions
+        .iter()
+        .all(|chunk_pos| stored_chunk(&world, chunk_pos).is_some())
+    {
+        assert!(
+            started.elapsed() < Duration::from_secs(30),
+            "chunks were lost"
+        );
+        world.receive_generated_chunks();
+        world.update_loaded_chunks(&chunk_view(100, 100));
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn edits_on_disk_win_over_regenerated_chunks() {
+    let directory = temp_directory("edits_on_disk_win");
+    let mut world = World::new().generate_world(
+        WorldGenerationType::InfinitePerlinTerrain,
+        WorldGenerationSize::Tiny,
+        WorldIslandSize::Small,
+        3,
+    );
+    world.use_region_storage(&directory).unwrap();
+    world.update_loaded_chunks(&chunk_view(0, 0));
+    let pos = GlobalTilePos(1, 1);
+    world.set_layer_tile(&pos, TileLayer::Terrain, Some(TileId::SNOW));
+    world.flush_dirty_chunks().unwrap();
+
+    // A second session of the same world generates the chunk again
+    let mut world = World::new().generate_world_in_background(
+        WorldGenerationType::InfinitePerlinTerrain,
+        WorldGenerationSize::Tiny,
+        WorldIslandSize::Small,
+        3,
+    );
+    world.use_region_storage(&directory).unwrap();
+    world.generate_chunk(&pos.chunk_pos());
+    let started = Instant::now();
+    while !world.chunks.contains_key(&pos.chunk_pos()) {
+        assert!(started.elapsed() < Duration::from_secs(30));
+        world.receive_generated_chunks();
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(world.get_tile(&pos), Some(&TileId::SNOW));
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn missing_chunks_are_not_looked_up_on_disk_again() {
+    let directory = temp_directory("missing_chunks_are_remembered");
+    let mut world = World::new();
+    world
+        .chunks
+        .insert(ChunkPos { x: 0, y: 0 }, Chunk::filled(TileId::SAND));
+    world.use_region_storage(&directory).unwrap();
+    let missing = ChunkPos { x: 1, y: 0 };
+    assert!(stored_chunk(&world, &missing).is_none());
+
+    // The region's offset table was read already, so the broken file isn't opened for it
+    let region_file = std::fs::read_dir(&directory).unwrap().next().unwrap();
+    std::fs::write(region_file.unwrap().path(), b"broken").unwrap();
+    assert!(stored_chunk(&world, &missing).is_none());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
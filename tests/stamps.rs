@@ -0,0 +1,210 @@
+mod common;
+
+use common::*;
+use world_renderer::edit_history::EditHistory;
+use world_renderer::stamp::*;
+use world_renderer::world::*;
+
+// A 3 by 2 stamp with a different terrain tile in every spot and a tree in its top left
+//   WATER SAND  STONE
+//   SNOW  GRASS DARK_STONE
+fn sample_stamp() -> Stamp {
+    let mut stamp = Stamp::new(3, 2).unwrap();
+    let tiles = [
+        TileId::WATER,
+        TileId::SAND,
+        TileId::STONE,
+        TileId::SNOW,
+        TileId::GRASS,
+        TileId::DARK_STONE,
+    ];
+    for (index, tile) in tiles.into_iter().enumerate() {
+        stamp.set(
+            index as u32 % 3,
+            index as u32 / 3,
+            TileLayer::Terrain,
+            Some(tile),
+        );
+    }
+    stamp.set(0, 0, TileLayer::Object, Some(TileId::TREE));
+    stamp
+}
+
+fn terrain_rows(stamp: &Stamp) -> Vec<Vec<TileId>> {
+    (0..stamp.height)
+        .map(|y| {
+            (0..stamp.width)
+                .map(|x| stamp.get(x, y, TileLayer::Terrain).unwrap())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn copying_crosses_chunk_boundaries() {
+    let mut world = grass_world_around_origin();
+    world.set_layer_tile(
+        &GlobalTilePos(-1, -1),
+        TileLayer::Terrain,
+        Some(TileId::SAND),
+    );
+    world.set_layer_tile(
+        &GlobalTilePos(0, 0),
+        TileLayer::Decoration,
+        Some(TileId::FLOWERS),
+    );
+
+    // Corners in any order
+    let stamp = Stamp::copy(&world, &GlobalTilePos(1, 1), &GlobalTilePos(-2, -2)).unwrap();
+    assert_eq!((stamp.width, stamp.height), (4, 4));
+    assert_eq!(stamp.get(1, 1, TileLayer::Terrain), Some(TileId::SAND));
+    assert_eq!(
+        stamp.get(2, 2, TileLayer::Decoration),
+        Some(TileId::FLOWERS)
+    );
+    assert_eq!(stamp.get(3, 3, TileLayer::Terrain), Some(TileId::GRASS));
+    assert_eq!(stamp.get(3, 3, TileLayer::Object), None);
+
+    // Nothing is copied from chunks that aren't loaded
+    let outside = Stamp::copy(&world, &GlobalTilePos(15, 0), &GlobalTilePos(16, 0)).unwrap();
+    assert_eq!(outside.get(0, 0, TileLayer::Terrain), Some(TileId::GRASS));
+    assert_eq!(outside.get(1, 0, TileLayer::Terrain), None);
+}
+
+#[test]
+fn stamps_have_a_size_limit() {
+    assert!(Stamp::new(0, 3).is_err());
+    assert!(Stamp::new(3, 0).is_err());
+    assert!(Stamp::new(MAX_STAMP_SIZE + 1, 1).is_err());
+    let thin = Stamp::new(MAX_STAMP_SIZE, 1).unwrap();
+    assert_eq!(thin.rotated_clockwise().height, MAX_STAMP_SIZE);
+
+    let world = grass_world_around_origin();
+    let corner = GlobalTilePos(0, 0);
+    let edge = MAX_STAMP_SIZE as i32 - 1;
+    assert!(Stamp::copy(&world, &corner, &GlobalTilePos(edge, 2)).is_ok());
+    assert!(Stamp::copy(&world, &corner, &GlobalTilePos(edge + 1, 2)).is_err());
+    // Areas whose size doesn't fit in a u32
+    let far = GlobalTilePos(i32::MIN, i32::MAX);
+    assert!(Stamp::copy(&world, &GlobalTilePos(i32::MAX, i32::MIN), &far).is_err());
+}
+
+#[test]
+fn stamps_rotate_and_flip() {
+    let stamp = sample_stamp();
+    let rotated = stamp.rotated_clockwise();
+    assert_eq!((rotated.width, rotated.height), (2, 3));
+    assert_eq!(
+        terrain_rows(&rotated),
+        vec![
+            vec![TileId::SNOW, TileId::WATER],
+            vec![TileId::GRASS, TileId::SAND],
+            vec![TileId::DARK_STONE, TileId::STONE],
+        ]
+    );
+    // Overlays turn with the terrain
+    assert_eq!(rotated.get(1, 0, TileLayer::Object), Some(TileId::TREE));
+    let full_turn = (0..4).fold(stamp.clone(), |stamp, _| stamp.rotated_clockwise());
+    assert_eq!(full_turn, stamp);
+
+    assert_eq!(
+        terrain_rows(&stamp.flipped_horizontally()),
+        vec![
+            vec![TileId::STONE, TileId::SAND, TileId::WATER],
+            vec![TileId::DARK_STONE, TileId::GRASS, TileId::SNOW],
+        ]
+    );
+    assert_eq!(
+        terrain_rows(&stamp.flipped_vertically()),
+        vec![
+            vec![TileId::SNOW, TileId::GRASS, TileId::DARK_STONE],
+            vec![TileId::WATER, TileId::SAND, TileId::STONE],
+        ]
+    );
+    // Flipping both ways is half a turn
+    assert_eq!(
+        stamp.flipped_horizontally().flipped_vertically(),
+        stamp.rotated_clockwise().rotated_clockwise()
+    );
+}
+
+#[test]
+fn pasting_is_one_undoable_action() {
+    let mut world = grass_world_around_origin();
+    let mut history = EditHistory::new(10);
+    world.set_layer_tile(&GlobalTilePos(1, 0), TileLayer::Object, Some(TileId::ROCK));
+
+    // Straddles all four chunks
+    let top_left = GlobalTilePos(-1, -1);
+    sample_stamp().paste(&mut world, &mut history, &top_left);
+    assert_eq!(history.len(), 1);
+    assert_eq!(world.get_tile(&GlobalTilePos(-1, -1)), Some(&TileId::WATER));
+    assert_eq!(
+        world.get_tile(&GlobalTilePos(1, 0)),
+        Some(&TileId::DARK_STONE)
+    );
+    assert_eq!(
+        world.get_layer_tile(&GlobalTilePos(-1, -1), TileLayer::Object),
+        Some(TileId::TREE)
+    );
+    // Empty overlay spots of the stamp clear the world's
+    assert_eq!(
+        world.get_layer_tile(&GlobalTilePos(1, 0), TileLayer::Object),
+        None
+    );
+
+    history.undo(&mut world);
+    assert_eq!(world.get_tile(&GlobalTilePos(-1, -1)), Some(&TileId::GRASS));
+    assert_eq!(
+        world.get_layer_tile(&GlobalTilePos(1, 0), TileLayer::Object),
+        Some(TileId::ROCK)
+    );
+}
+
+#[test]
+fn pasting_skips_terrain_that_was_not_copied() {
+    let mut stamp = Stamp::new(2, 1).unwrap();
+    stamp.set(0, 0, TileLayer::Terrain, Some(TileId::SAND));
+    let placements = stamp.placements(&GlobalTilePos(5, 5));
+    assert!(placements.contains(&(GlobalTilePos(5, 5), TileLayer::Terrain, Some(TileId::SAND))));
+    assert!(!placements
+        .iter()
+        .any(|(pos, layer, _)| *pos == GlobalTilePos(6, 5) && *layer == TileLayer::Terrain));
+    assert_eq!(placements.len(), 1 + 2 * TileLayer::OVERLAYS.len());
+}
+
+#[test]
+fn stamp_files_round_trip() {
+    let stamp = sample_stamp().rotated_clockwise();
+    let mut bytes = Vec::new();
+    stamp.write_to(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 10 + 6 * 3 * 2);
+    assert_eq!(Stamp::read_from(&mut bytes.as_slice()).unwrap(), stamp);
+
+    // Unknown tiles and sizes that don't fit are refused
+    let mut unknown_tile = bytes.clone();
+    unknown_tile[10..12].copy_from_slice(&4000u16.to_le_bytes());
+    assert!(Stamp::read_from(&mut unknown_tile.as_slice()).is_err());
+    let mut empty = bytes.clone();
+    empty[6..8].copy_from_slice(&0u16.to_le_bytes());
+    assert!(Stamp::read_from(&mut empty.as_slice()).is_err());
+    assert!(Stamp::read_from(&mut &bytes[..bytes.len() - 1]).is_err());
+    assert!(Stamp::read_from(&mut &b"WRLD"[..]).is_err());
+}
+
+#[test]
+fn stamp_library_lists_stamp_files() {
+    let directory = std::env::temp_dir().join(format!("stamp_library_{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    sample_stamp().save(directory.join("b.stamp")).unwrap();
+    sample_stamp().save(directory.join("a.stamp")).unwrap();
+    std::fs::write(directory.join("notes.txt"), "not a stamp").unwrap();
+
+    let stamps = list_stamps(&directory).unwrap();
+    assert_eq!(
+        stamps,
+        vec![directory.join("a.stamp"), directory.join("b.stamp")]
+    );
+    assert_eq!(Stamp::load(&stamps[0]).unwrap(), sample_stamp());
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,85 @@
+use proptest::prelude::*;
+use world_renderer::world::*;
+
+// Tiles within a few chunks of the origin, so every quadrant and chunk border is covered
+fn tile_pos() -> impl Strategy<Value = GlobalTilePos> {
+    (-64..64, -64..64).prop_map(|(x, y)| GlobalTilePos(x, y))
+}
+
+// A world with chunks around the origin where every tile is Grass, except for the tile at pos
+fn world_with_marked_tile(pos: &GlobalTilePos) -> World {
+    let mut world = World::new();
+    for chunk_y in -5..5 {
+        for chunk_x in -5..5 {
+            let chunk = Chunk::filled(TileId::GRASS);
+            world.chunks.insert(
+                ChunkPos {
+                    x: chunk_x,
+                    y: chunk_y,
+                },
+                chunk,
+            );
+        }
+    }
+    world.set_layer_tile(pos, TileLayer::Terrain, Some(TileId::SNOW));
+    world
+}
+
+#[test]
+fn negative_tiles_belong_to_negative_chunks() {
+    let (chunk_pos, local_index) = GlobalTilePos(-1, -1).to_chunk_local();
+    assert_eq!(chunk_pos, ChunkPos { x: -1, y: -1 });
+    assert_eq!(local_index, 255);
+
+    let (chunk_pos, local_index) = GlobalTilePos(-16, 15).to_chunk_local();
+    assert_eq!(chunk_pos, ChunkPos { x: -1, y: 0 });
+    assert_eq!(local_index, 15 * 16);
+
+    let (chunk_pos, local_index) = GlobalTilePos(-17, -16).to_chunk_local();
+    assert_eq!(chunk_pos, ChunkPos { x: -2, y: -1 });
+    assert_eq!(local_index, 15);
+}
+
+proptest! {
+    #[test]
+    fn local_index_is_inside_the_chunk(pos in tile_pos()) {
+        let (_, local_index) = pos.to_chunk_local();
+        prop_assert!(local_index < 256);
+    }
+
+    #[test]
+    fn chunk_local_round_trips(pos in tile_pos()) {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        prop_assert_eq!(chunk_pos.tile_pos(local_index), pos);
+    }
+
+    #[test]
+    fn neighbours_are_in_the_same_or_next_chunk(pos in tile_pos()) {
+        let (chunk_pos, local_index) = pos.to_chunk_local();
+        let (right_chunk, right_index) = GlobalTilePos(pos.0 + 1, pos.1).to_chunk_local();
+        let (below_chunk, below_index) = GlobalTilePos(pos.0, pos.1 + 1).to_chunk_local();
+        if local_index % 16 == 15 {
+            prop_assert_eq!(right_chunk, ChunkPos { x: chunk_pos.x + 1, y: chunk_pos.y });
+            prop_assert_eq!(right_index, local_index - 15);
+        } else {
+            prop_assert_eq!(right_chunk, chunk_pos);
+            prop_assert_eq!(right_index, local_index + 1);
+        }
+        if local_index / 16 == 15 {
+            prop_assert_eq!(below_chunk, ChunkPos { x: chunk_pos.x, y: chunk_pos.y + 1 });
+            prop_assert_eq!(below_index, local_index - 15 * 16);
+        } else {
+            prop_assert_eq!(below_chunk, chunk_pos);
+            prop_assert_eq!(below_index, local_index + 16);
+        }
+    }
+
+    #[test]
+    fn edited_tile_is_read_back_at_the_same_position(pos in tile_pos(), other in tile_pos()) {
+        let mut world = world_with_marked_tile(&pos);
+        prop_assert_eq!(world.get_tile(&pos), Some(&TileId::SNOW));
+        if other != pos {
+            prop_assert_eq!(world.get_tile(&other), Some(&TileId::GRASS));
+        }
+    }
+}
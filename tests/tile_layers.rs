@@ -0,0 +1,104 @@
+mod common;
+
+use common::*;
+use world_renderer::world::*;
+
+#[test]
+fn layers_are_set_independently() {
+    let mut world = grass_world();
+    let pos = GlobalTilePos(3, 5);
+    assert!(world.set_layer_tile(&pos, TileLayer::Decoration, Some(TileId::FLOWERS)));
+    assert!(world.set_layer_tile(&pos, TileLayer::Object, Some(TileId::TREE)));
+
+    assert_eq!(
+        world.get_layer_tile(&pos, TileLayer::Terrain),
+        Some(TileId::GRASS)
+    );
+    assert_eq!(
+        world.get_layer_tile(&pos, TileLayer::Decoration),
+        Some(TileId::FLOWERS)
+    );
+    assert_eq!(
+        world.get_layer_tile(&pos, TileLayer::Object),
+        Some(TileId::TREE)
+    );
+    assert_eq!(world.get_top_tile(&pos), Some(TileId::TREE));
+
+    assert!(world.set_layer_tile(&pos, TileLayer::Object, None));
+    assert_eq!(world.get_layer_tile(&pos, TileLayer::Object), None);
+    assert_eq!(world.get_top_tile(&pos), Some(TileId::FLOWERS));
+    assert_eq!(
+        world.get_layer_tile(&GlobalTilePos(4, 5), TileLayer::Decoration),
+        None
+    );
+}
+
+#[test]
+fn terrain_cant_be_cleared() {
+    let mut world = grass_world();
+    let pos = GlobalTilePos(0, 0);
+    assert!(!world.set_layer_tile(&pos, TileLayer::Terrain, None));
+    assert!(world.set_layer_tile(&pos, TileLayer::Terrain, Some(TileId::SAND)));
+    assert_eq!(world.get_tile(&pos), Some(&TileId::SAND));
+    assert!(!world.set_layer_tile(
+        &GlobalTilePos(-1, 0),
+        TileLayer::Decoration,
+        Some(TileId::ROCK)
+    ));
+}
+
+#[test]
+fn layers_survive_saving() {
+    let mut world = grass_world();
+    world.set_layer_tile(
+        &GlobalTilePos(1, 2),
+        TileLayer::Decoration,
+        Some(TileId::ROCK),
+    );
+    world.set_layer_tile(
+        &GlobalTilePos(15, 15),
+        TileLayer::Object,
+        Some(TileId::TREE),
+    );
+
+    let mut bytes = Vec::new();
+    world.write_to(&mut bytes).unwrap();
+    let loaded = World::read_from(&mut bytes.as_slice()).unwrap();
+    let chunk = &loaded.chunks[&ChunkPos { x: 0, y: 0 }];
+    assert_eq!(
+        chunk.decorations.iter().collect::<Vec<_>>(),
+        vec![(33, TileId::ROCK)]
+    );
+    assert_eq!(
+        chunk.objects.iter().collect::<Vec<_>>(),
+        vec![(255, TileId::TREE)]
+    );
+}
+
+#[test]
+fn random_worlds_only_use_terrain_tiles() {
+    for generation_type in [
+        WorldGenerationType::ChunkMess,
+        WorldGenerationType::TileMess,
+    ] {
+        let world = World::new().generate_world(
+            generation_type,
+            WorldGenerationSize::Small,
+            WorldIslandSize::Small,
+            11,
+        );
+        for chunk in world.chunks.values() {
+            for tile in chunk.tiles.iter() {
+                assert_eq!(tile.definition().unwrap().layer, TileLayer::Terrain);
+            }
+        }
+    }
+}
+
+#[test]
+fn height_bands_only_take_terrain_tiles() {
+    let mut settings = PerlinWorldSettings::default();
+    assert!(settings.validate().is_ok());
+    settings.height_bands[1].tile = TileId::TREE;
+    assert!(settings.validate().is_err());
+}
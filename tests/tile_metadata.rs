@@ -0,0 +1,88 @@
+mod common;
+
+use common::*;
+use world_renderer::tile_metadata::*;
+use world_renderer::world::*;
+
+fn sign_text(text: &str) -> TileMetadata {
+    let mut metadata = TileMetadata::default();
+    metadata.set(TEXT, MetadataValue::Text(text.to_string()));
+    metadata
+}
+
+#[test]
+fn metadata_is_kept_per_tile_and_layer() {
+    let mut world = grass_world_around_origin();
+    let pos = GlobalTilePos(-3, 4);
+    world.set_layer_tile(&pos, TileLayer::Object, Some(TileId::TREE));
+    world
+        .tile_metadata_mut(&pos, TileLayer::Object)
+        .unwrap()
+        .set(GROWTH_STAGE, MetadataValue::Integer(2));
+    assert!(world.set_tile_metadata(&pos, TileLayer::Terrain, sign_text("Keep off the grass")));
+
+    let tree = world.get_tile_metadata(&pos, TileLayer::Object).unwrap();
+    assert_eq!(tree.integer(GROWTH_STAGE), Some(2));
+    let ground = world.get_tile_metadata(&pos, TileLayer::Terrain).unwrap();
+    assert_eq!(ground.text(TEXT), Some("Keep off the grass"));
+    assert!(world
+        .get_tile_metadata(&GlobalTilePos(-2, 4), TileLayer::Terrain)
+        .is_none());
+}
+
+#[test]
+fn empty_spots_have_no_metadata() {
+    let mut world = grass_world_around_origin();
+    let pos = GlobalTilePos(-1, 1);
+    assert!(world
+        .tile_metadata_mut(&pos, TileLayer::Decoration)
+        .is_none());
+    assert!(!world.set_tile_metadata(
+        &GlobalTilePos(16, 0),
+        TileLayer::Terrain,
+        sign_text("Unloaded")
+    ));
+}
+
+#[test]
+fn replacing_a_tile_drops_its_metadata() {
+    let mut world = grass_world_around_origin();
+    let pos = GlobalTilePos(-16, 15);
+    world.set_layer_tile(&pos, TileLayer::Object, Some(TileId::TREE));
+    world.set_tile_metadata(&pos, TileLayer::Object, sign_text("Old oak"));
+    world.set_tile_metadata(&pos, TileLayer::Terrain, sign_text("Meadow"));
+
+    // Setting the same tile again isn't a replacement
+    world.set_layer_tile(&pos, TileLayer::Object, Some(TileId::TREE));
+    assert!(world.get_tile_metadata(&pos, TileLayer::Object).is_some());
+
+    world.set_layer_tile(&pos, TileLayer::Object, None);
+    assert!(world.get_tile_metadata(&pos, TileLayer::Object).is_none());
+    assert!(world.get_tile_metadata(&pos, TileLayer::Terrain).is_some());
+
+    world.set_layer_tile(&pos, TileLayer::Terrain, Some(TileId::SAND));
+    assert!(world.get_tile_metadata(&pos, TileLayer::Terrain).is_none());
+}
+
+#[test]
+fn metadata_survives_saving() {
+    let mut world = grass_world_around_origin();
+    let pos = GlobalTilePos(-7, 9);
+    let mut metadata = sign_text("Welcome");
+    metadata.set(OWNER, MetadataValue::Text("Ada".to_string()));
+    metadata.set(DAMAGE, MetadataValue::Integer(-40));
+    world.set_tile_metadata(&pos, TileLayer::Terrain, metadata.clone());
+    // Emptied metadata isn't written
+    world.tile_metadata_mut(&GlobalTilePos(-8, 9), TileLayer::Terrain);
+
+    let mut bytes = Vec::new();
+    world.write_to(&mut bytes).unwrap();
+    let loaded = World::read_from(&mut bytes.as_slice()).unwrap();
+    assert_eq!(
+        loaded.get_tile_metadata(&pos, TileLayer::Terrain),
+        Some(&metadata)
+    );
+    assert!(loaded
+        .get_tile_metadata(&GlobalTilePos(-8, 9), TileLayer::Terrain)
+        .is_none());
+}
@@ -0,0 +1,57 @@
+use world_renderer::tile_registry::*;
+use world_renderer::world::TileLayer;
+
+fn definition(id: u16, name: &str) -> TileDefinition {
+    TileDefinition {
+        id: TileId(id),
+        name: name.to_string(),
+        atlas: [3, 2],
+        color: [200, 60, 20],
+        layer: TileLayer::Terrain,
+        walkable: true,
+        liquid: false,
+        solid: false,
+        animation: None,
+    }
+}
+
+#[test]
+fn tiles_with_large_ids_get_compact_atlas_rows() {
+    let mut registry = TileRegistry::builtin();
+    let builtin_count = registry.len();
+    registry
+        .add_from_toml_str(
+            r#"
+            [[tile]]
+            id = 60000
+            name = "Lava"
+            atlas = [3, 2]
+            color = [200, 60, 20]
+            "#,
+        )
+        .unwrap();
+    registry.add(definition(500, "Ash")).unwrap();
+
+    // Rows follow the ids, whatever the order tiles were added in
+    assert_eq!(registry.atlas_row(TileId::GRASS), Some(0));
+    assert_eq!(registry.atlas_row(TileId(500)), Some(builtin_count as u16));
+    assert_eq!(
+        registry.atlas_row(TileId(60000)),
+        Some(builtin_count as u16 + 1)
+    );
+    assert_eq!(registry.atlas_row(TileId(60001)), None);
+}
+
+#[test]
+fn registries_have_room_for_max_tiles() {
+    let mut registry = TileRegistry::builtin();
+    let mut id = 1000;
+    while registry.len() < MAX_TILES {
+        registry
+            .add(definition(id, &format!("Tile {}", id)))
+            .unwrap();
+        id += 1;
+    }
+    assert!(registry.add(definition(id, "One too many")).is_err());
+    assert_eq!(registry.len(), MAX_TILES);
+}
@@ -0,0 +1,108 @@
+use world_renderer::world::*;
+
+// A world with every chunk from -2 to 1 on both axes loaded
+fn world_around_origin() -> World {
+    let mut world = World::new();
+    for chunk_y in -2..2 {
+        for chunk_x in -2..2 {
+            world.chunks.insert(
+                ChunkPos {
+                    x: chunk_x,
+                    y: chunk_y,
+                },
+                Chunk::filled(TileId::GRASS),
+            );
+        }
+    }
+    world
+}
+
+fn visible_chunk_positions(world: &World, view: &ViewRect) -> Vec<(i32, i32)> {
+    world
+        .visible_chunks(view)
+        .map(|(chunk_pos, _)| (chunk_pos.x, chunk_pos.y))
+        .collect()
+}
+
+#[test]
+fn view_straddling_the_origin_sees_all_four_quadrants() {
+    let view = ViewRect {
+        left: -0.5,
+        top: -0.5,
+        right: 0.5,
+        bottom: 0.5,
+    };
+    assert_eq!(
+        visible_chunk_positions(&world_around_origin(), &view),
+        vec![(-1, -1), (0, -1), (-1, 0), (0, 0)]
+    );
+}
+
+#[test]
+fn view_ending_on_a_chunk_border_does_not_see_the_next_chunk() {
+    let view = ViewRect {
+        left: -16.0,
+        top: -32.0,
+        right: 0.0,
+        bottom: -16.0,
+    };
+    assert_eq!(
+        visible_chunk_positions(&world_around_origin(), &view),
+        vec![(-1, -2)]
+    );
+}
+
+#[test]
+fn view_across_a_negative_chunk_border() {
+    let view = ViewRect {
+        left: -17.0,
+        top: -1.0,
+        right: -15.5,
+        bottom: -0.25,
+    };
+    let (top_left, bottom_right) = view.tile_bounds();
+    assert_eq!(top_left, GlobalTilePos(-17, -1));
+    assert_eq!(bottom_right, GlobalTilePos(-16, -1));
+    assert_eq!(
+        visible_chunk_positions(&world_around_origin(), &view),
+        vec![(-2, -1), (-1, -1)]
+    );
+}
+
+#[test]
+fn chunks_that_are_not_loaded_are_skipped() {
+    let view = ViewRect {
+        left: -100.0,
+        top: -100.0,
+        right: 100.0,
+        bottom: 100.0,
+    };
+    let world = world_around_origin();
+    assert_eq!(world.visible_chunks(&view).count(), 16);
+    assert_eq!(World::new().visible_chunks(&view).count(), 0);
+}
+
+#[test]
+fn empty_view_sees_nothing() {
+    let view = ViewRect {
+        left: -3.0,
+        top: -3.0,
+        right: -3.0,
+        bottom: 2.0,
+    };
+    assert_eq!(world_around_origin().visible_chunks(&view).count(), 0);
+}
+
+#[test]
+fn chunk_tiles_iterate_with_global_positions() {
+    let chunk = Chunk::filled(TileId::SAND);
+    let positions: Vec<GlobalTilePos> = chunk
+        .iter_tiles(&ChunkPos { x: -1, y: -2 })
+        .map(|(pos, _)| pos)
+        .collect();
+    assert_eq!(positions.len(), 256);
+    assert_eq!(positions[0], GlobalTilePos(-16, -32));
+    assert_eq!(positions[1], GlobalTilePos(-15, -32));
+    assert_eq!(positions[16], GlobalTilePos(-16, -31));
+    assert_eq!(positions[255], GlobalTilePos(-1, -17));
+}
@@ -0,0 +1,55 @@
+use world_renderer::world::*;
+use world_renderer::world_preset::*;
+
+fn preset_with_bands(bands: &str) -> Result<WorldPreset, PresetError> {
+    WorldPreset::from_toml_str(&format!(
+        r#"
+        name = "Bands"
+        generation_type = "PerlinTerrain"
+        size = "Tiny"
+        island_size = "Small"
+        seed = 3
+
+        [perlin]
+        height_bands = [{}]
+        "#,
+        bands
+    ))
+}
+
+#[test]
+fn shipped_presets_load() {
+    let directory = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("presets");
+    let mut count = 0;
+    for entry in std::fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if let Err(err) = WorldPreset::load(&path) {
+            panic!("{}: {}", path.display(), err);
+        }
+        count += 1;
+    }
+    assert!(count > 0);
+}
+
+#[test]
+fn band_tables_must_be_sorted_and_not_overlap() {
+    let preset = preset_with_bands(
+        r#"{ min_height = -inf, tile = "Water" }, { min_height = 0.5, tile = "Sand" }"#,
+    )
+    .unwrap();
+    assert_eq!(preset.perlin.tile_at_height(0.6), TileId::SAND);
+
+    let message = |bands| match preset_with_bands(bands) {
+        Err(PresetError::Invalid(message)) => message,
+        other => panic!("{} was accepted: {:?}", bands, other),
+    };
+    let unsorted =
+        message(r#"{ min_height = 0.5, tile = "Sand" }, { min_height = 0.1, tile = "Water" }"#);
+    assert!(unsorted.contains("not sorted"), "{}", unsorted);
+    assert!(unsorted.contains("Sand") && unsorted.contains("Water"));
+    let overlapping =
+        message(r#"{ min_height = 0.5, tile = "Sand" }, { min_height = 0.5, tile = "Grass" }"#);
+    assert!(overlapping.contains("overlap"), "{}", overlapping);
+    assert!(overlapping.contains("Grass"));
+    message("");
+}
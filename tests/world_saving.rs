@@ -0,0 +1,46 @@
+use world_renderer::world::*;
+
+#[test]
+fn perlin_worlds_round_trip_tile_for_tile() {
+    let world = World::new().generate_world(
+        WorldGenerationType::PerlinTerrain,
+        WorldGenerationSize::Small,
+        WorldIslandSize::Small,
+        7,
+    );
+    assert!(!world.chunks.is_empty());
+
+    let mut bytes = Vec::new();
+    world.write_to(&mut bytes).unwrap();
+    let loaded = World::read_from(&mut bytes.as_slice()).unwrap();
+
+    assert_eq!(loaded.seed, world.seed);
+    assert_eq!(loaded.generation_type, world.generation_type);
+    assert_eq!(loaded.island_size, world.island_size);
+    assert_eq!(loaded.perlin_settings, world.perlin_settings);
+    assert_eq!(loaded.chunks.len(), world.chunks.len());
+    for (chunk_pos, chunk) in &world.chunks {
+        let loaded_chunk = &loaded.chunks[chunk_pos];
+        assert_eq!(
+            loaded_chunk.tiles, chunk.tiles,
+            "terrain of {:?}",
+            chunk_pos
+        );
+        assert_eq!(loaded_chunk.decorations, chunk.decorations);
+        assert_eq!(loaded_chunk.objects, chunk.objects);
+        assert_eq!(loaded_chunk.metadata, chunk.metadata);
+    }
+    // Saving the loaded world gives the same file
+    let mut saved_again = Vec::new();
+    loaded.write_to(&mut saved_again).unwrap();
+    assert_eq!(saved_again, bytes);
+}
+
+#[test]
+fn other_world_file_versions_are_refused() {
+    let mut bytes = Vec::new();
+    World::new().write_to(&mut bytes).unwrap();
+    assert!(World::read_from(&mut bytes.as_slice()).is_ok());
+    bytes[4..6].copy_from_slice(&2u16.to_le_bytes());
+    assert!(World::read_from(&mut bytes.as_slice()).is_err());
+}